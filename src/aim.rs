@@ -0,0 +1,118 @@
+use firewors::sim::{Vector, APEX_SPEED, REFERENCE_DT};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    widgets::Widget,
+};
+
+// furthest the launcher tilts from straight up, in degrees, and the least
+// of the top launch speed it can fire at
+const MAX_TILT: f64 = 60.0;
+const MIN_POWER: f64 = 0.3;
+// simulation steps between two dots of the predicted path, and the most
+// steps it is followed for
+const DOT_STEPS: usize = 8;
+const MAX_STEPS: usize = 3000;
+
+const SIGHT: Color = Color::Rgb(110, 110, 110);
+const MARKER: Color = Color::Rgb(230, 230, 230);
+
+/// A launcher on the ground that fires rockets where it is aimed.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Launcher {
+    // as a share of the screen width, 0 being the middle
+    pub x: f64,
+    // degrees from straight up, positive to the right
+    pub angle: f64,
+    // share of the top launch speed
+    pub power: f64,
+}
+
+impl Launcher {
+    pub fn new() -> Launcher {
+        Launcher {
+            x: 0.0,
+            angle: 0.0,
+            power: 0.9,
+        }
+    }
+
+    pub fn nudge(&mut self, dx: f64) {
+        self.x = (self.x + dx).clamp(-0.5, 0.5);
+    }
+
+    pub fn tilt(&mut self, degrees: f64) {
+        self.angle = (self.angle + degrees).clamp(-MAX_TILT, MAX_TILT);
+    }
+
+    pub fn push(&mut self, power: f64) {
+        self.power = (self.power + power).clamp(MIN_POWER, 1.0);
+    }
+
+    pub fn velocity(&self, max_speed: f64) -> Vector {
+        let angle = self.angle.to_radians();
+        Vector {
+            x: angle.sin(),
+            y: angle.cos(),
+        } * (max_speed * self.power)
+    }
+}
+
+/// Where a rocket leaving `pos` at `vel` goes until it bursts, pulled by
+/// `force` (gravity and the wind) the way the simulation moves it: a dot
+/// every few steps, the last one where it goes off, give or take the apex
+/// fuzz.
+pub fn path(pos: Vector, vel: Vector, force: Vector) -> Vec<Vector> {
+    let (mut pos, mut vel) = (pos, vel);
+    let mut dots = Vec::new();
+    for step in 1..=MAX_STEPS {
+        vel = vel + force * REFERENCE_DT;
+        pos = pos + vel * REFERENCE_DT;
+        // rockets burst just past their apex, or about there
+        if vel.y <= -APEX_SPEED {
+            dots.push(pos);
+            break;
+        }
+        if step % DOT_STEPS == 0 {
+            dots.push(pos);
+        }
+    }
+    dots
+}
+
+/// The launcher at the bottom of its area and the path of its next rocket,
+/// in canvas coordinates centered on the area, with `color` picking what
+/// its colors come out as.
+pub struct Sight<'a, F: Fn(Color) -> Color> {
+    pub launcher: Vector,
+    pub path: &'a [Vector],
+    pub color: F,
+}
+
+impl<F: Fn(Color) -> Color> Widget for Sight<'_, F> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let mut put = |pos: Vector, glyph: char, color: Color| {
+            let column = (pos.x + f64::from(area.width) / 2.0).floor();
+            let row = (f64::from(area.height) / 2.0 - pos.y).floor();
+            if (0.0..f64::from(area.width)).contains(&column)
+                && (0.0..f64::from(area.height)).contains(&row)
+            {
+                buf[(area.x + column as u16, area.y + row as u16)]
+                    .set_char(glyph)
+                    .set_style(Style::default().fg((self.color)(color)));
+            }
+        };
+        if let Some((burst, dots)) = self.path.split_last() {
+            for dot in dots {
+                put(*dot, '·', SIGHT);
+            }
+            put(*burst, '*', SIGHT);
+        }
+        let ground = Vector {
+            x: self.launcher.x,
+            y: -f64::from(area.height) / 2.0 + 0.5,
+        };
+        put(ground, '▲', MARKER);
+    }
+}
@@ -0,0 +1,168 @@
+use clap::ValueEnum;
+use ratatui::style::Color;
+use std::env;
+
+// xterm's take on the 16 colors every terminal has
+const ANSI: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+// the levels each of red, green and blue take in xterm's 6x6x6 color cube,
+// which the 256 colors go on with after the 16, and then 24 grays
+const CUBE: [u8; 6] = [0, 95, 135, 175, 215, 255];
+const GRAYS: u8 = 232;
+
+#[derive(Debug, Copy, Clone, PartialEq, ValueEnum)]
+pub enum ColorMode {
+    /// As many colors as the terminal says it has
+    Auto,
+    /// 24-bit color
+    Truecolor,
+    /// The 256 colors of xterm
+    #[value(name = "256")]
+    Indexed,
+    /// Only the 16 basic terminal colors
+    Ansi,
+}
+
+/// How many colors the terminal draws with, the fewest first.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Depth {
+    Ansi,
+    Indexed,
+    Truecolor,
+}
+
+impl ColorMode {
+    pub fn depth(self) -> Depth {
+        match self {
+            ColorMode::Auto => detect(
+                env::var("COLORTERM").ok().as_deref(),
+                env::var("TERM").ok().as_deref(),
+            ),
+            ColorMode::Truecolor => Depth::Truecolor,
+            ColorMode::Indexed => Depth::Indexed,
+            ColorMode::Ansi => Depth::Ansi,
+        }
+    }
+}
+
+// what the terminal says it has, going by what terminals put in COLORTERM
+// and TERM for it
+fn detect(colorterm: Option<&str>, term: Option<&str>) -> Depth {
+    let term = term.unwrap_or_default();
+    if matches!(colorterm, Some("truecolor") | Some("24bit")) || term.ends_with("-direct") {
+        Depth::Truecolor
+    } else if term.contains("256color") {
+        Depth::Indexed
+    } else {
+        Depth::Ansi
+    }
+}
+
+/// `color` as red, green and blue, taking the 16 terminal colors and the
+/// 256 to look like they do in xterm.
+pub fn to_rgb(color: Color) -> (u8, u8, u8) {
+    match color {
+        Color::Rgb(r, g, b) => (r, g, b),
+        Color::Indexed(i) if i < 16 => ANSI[usize::from(i)].1,
+        Color::Indexed(i) if i < GRAYS => {
+            let i = usize::from(i - 16);
+            (CUBE[i / 36], CUBE[i / 6 % 6], CUBE[i % 6])
+        }
+        Color::Indexed(i) => {
+            let gray = 8 + 10 * (i - GRAYS);
+            (gray, gray, gray)
+        }
+        color => ANSI
+            .iter()
+            .find(|(ansi, _)| *ansi == color)
+            .map_or((255, 255, 255), |(_, rgb)| *rgb),
+    }
+}
+
+/// The closest of the 16 terminal colors to `color`.
+pub fn to_ansi(color: Color) -> Color {
+    let (r, g, b) = match color {
+        Color::Rgb(r, g, b) => (r, g, b),
+        color => return color,
+    };
+    let distance = |(_, (r2, g2, b2)): &&(Color, (u8, u8, u8))| {
+        let d = |a: u8, b: u8| (i32::from(a) - i32::from(b)).pow(2);
+        d(r, *r2) + d(g, *g2) + d(b, *b2)
+    };
+    ANSI.iter()
+        .min_by_key(distance)
+        .map_or(color, |(ansi, _)| *ansi)
+}
+
+/// The closest of the 256 colors to `color`, from the color cube or the
+/// grays; the 16 stay as they are.
+pub fn to_indexed(color: Color) -> Color {
+    let (r, g, b) = match color {
+        Color::Rgb(r, g, b) => (r, g, b),
+        color => return color,
+    };
+    let distance = |i: u8| {
+        let (r2, g2, b2) = to_rgb(Color::Indexed(i));
+        let d = |a: u8, b: u8| (i32::from(a) - i32::from(b)).pow(2);
+        d(r, r2) + d(g, g2) + d(b, b2)
+    };
+    (16..=255)
+        .min_by_key(|i| distance(*i))
+        .map_or(color, Color::Indexed)
+}
+
+/// The parameters of the escape code that sets `color`, as the background if
+/// `background` and otherwise as the foreground.
+pub fn sgr(color: Color, background: bool) -> String {
+    let base = if background { 40 } else { 30 };
+    match color {
+        Color::Reset => format!("{}", base + 9),
+        Color::Rgb(r, g, b) => format!("{};2;{};{};{}", base + 8, r, g, b),
+        Color::Indexed(i) => format!("{};5;{}", base + 8, i),
+        color => match ANSI.iter().position(|(ansi, _)| *ansi == color) {
+            Some(i) if i < 8 => format!("{}", base + i),
+            Some(i) => format!("{}", base + 60 + i - 8),
+            None => format!("{}", base + 9),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn goes_by_what_the_terminal_says() {
+        assert_eq!(detect(Some("truecolor"), Some("xterm")), Depth::Truecolor);
+        assert_eq!(detect(None, Some("xterm-direct")), Depth::Truecolor);
+        assert_eq!(detect(None, Some("screen-256color")), Depth::Indexed);
+        assert_eq!(detect(None, Some("linux")), Depth::Ansi);
+        assert_eq!(detect(None, None), Depth::Ansi);
+    }
+
+    #[test]
+    fn indexed_colors_come_close() {
+        assert_eq!(to_indexed(Color::Rgb(255, 135, 0)), Color::Indexed(208));
+        assert_eq!(to_indexed(Color::Rgb(128, 128, 128)), Color::Indexed(244));
+        assert_eq!(to_indexed(Color::Red), Color::Red);
+        assert_eq!(to_rgb(Color::Indexed(208)), (255, 135, 0));
+        assert_eq!(to_rgb(Color::Indexed(1)), (205, 0, 0));
+    }
+}
@@ -0,0 +1,330 @@
+use crate::ansi::ColorMode;
+use crate::backend::BackendKind;
+use crate::config::Preset;
+use clap::{Parser, Subcommand};
+use firewors::sim::Ground;
+use std::path::PathBuf;
+
+/// Fireworks in your terminal!
+#[derive(Debug, Parser)]
+#[command(name = "firewors")]
+pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Make the show reproducible
+    #[arg(long, value_name = "N")]
+    pub seed: Option<u64>,
+
+    /// Take settings from a preset in the config file
+    #[arg(long, value_name = "NAME")]
+    pub preset: Option<String>,
+
+    /// Use a named palette: classic, pastel, neon, mono, contrast, pride, deuteranopia, protanopia or one from the config file
+    #[arg(long, visible_alias = "palette", value_name = "NAME")]
+    pub theme: Option<String>,
+
+    /// Draw a skyline, stars and a moon behind the fireworks, whatever the config file says
+    #[arg(long)]
+    pub scenery: bool,
+
+    /// Mirror the fireworks in that many rows of water at the bottom of the screen
+    #[arg(long, value_name = "ROWS", value_parser = clap::value_parser!(u16).range(1..))]
+    pub reflection: Option<u16>,
+
+    /// Start with a launcher to aim and fire rockets from, steered with the arrow keys
+    #[arg(long)]
+    pub aim: bool,
+
+    /// Leave drifting smoke behind every burst
+    #[arg(long)]
+    pub smoke: bool,
+
+    /// Milliseconds between simulation steps [default: 10]
+    #[arg(long, value_name = "MS", value_parser = clap::value_parser!(u64).range(1..))]
+    pub tick_ms: Option<u64>,
+
+    /// Downward pull on every particle, in cells per second squared [default: 40]
+    #[arg(long, value_name = "X", value_parser = non_negative)]
+    pub gravity: Option<f64>,
+
+    /// Multiplies how quickly the air slows embers down [default: 1]
+    #[arg(long, value_name = "X", value_parser = non_negative)]
+    pub drag: Option<f64>,
+
+    /// Sideways push, in cells per second squared; negative blows to the left [default: 0]
+    #[arg(long, value_name = "X", allow_negative_numbers = true, value_parser = finite)]
+    pub wind: Option<f64>,
+
+    /// How hard embers get knocked about at random [default: 0]
+    #[arg(long, value_name = "X", value_parser = non_negative)]
+    pub turbulence: Option<f64>,
+
+    /// Most particles alive at once in each color [default: 1000]
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    pub particles: Option<u64>,
+
+    /// Embers in a burst, before launch speed and --burst-scale resize it
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    pub subparticles: Option<u64>,
+
+    /// Stages every firework explodes in, from 1 to 3 [default: 1]
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u8).range(1..=3))]
+    pub depth: Option<u8>,
+
+    /// Make rockets climb faster (> 1) or slower (< 1)
+    #[arg(long, value_name = "X", value_parser = positive)]
+    pub launch_speed: Option<f64>,
+
+    /// Make every burst bigger (> 1) or smaller (< 1)
+    #[arg(long, value_name = "X", value_parser = positive)]
+    pub burst_scale: Option<f64>,
+
+    /// Smallest and biggest shell, times a burst's usual number of embers [default: 0.8,3]
+    #[arg(long, value_name = "MIN,MAX", value_parser = sizes)]
+    pub shell_sizes: Option<[f64; 2]>,
+
+    /// How far from just past its apex a rocket may burst, in cells per second [default: 10]
+    #[arg(long, value_name = "X", value_parser = non_negative)]
+    pub apex_fuzz: Option<f64>,
+
+    /// What embers do at the bottom of the screen: through, bounce or slide [default: through]
+    #[arg(long, value_name = "MODE", value_parser = ground)]
+    pub ground: Option<Ground>,
+
+    /// Bring particles leaving one side of the screen back in at the other
+    #[arg(long)]
+    pub wrap: bool,
+
+    /// Text for `w` to launch, spelled out in sparks
+    #[arg(long, value_name = "TEXT")]
+    pub message: Option<String>,
+
+    /// How many colors to draw with
+    #[arg(long, value_enum, value_name = "MODE", default_value_t = ColorMode::Auto)]
+    pub colors: ColorMode,
+
+    /// What to talk to the terminal with
+    #[arg(long, value_enum, value_name = "NAME", default_value_t = BackendKind::Crossterm)]
+    pub backend: BackendKind,
+
+    /// Keep particles, burst density and the auto show's pace the same whatever the screen size and frame rate
+    #[arg(long)]
+    pub no_adapt: bool,
+
+    /// Slower, fewer and steady sparks that never flash or glitter
+    #[arg(long)]
+    pub reduced_motion: bool,
+
+    /// Bold colors drawn as solid blocks, with the dim ones left out
+    #[arg(long)]
+    pub high_contrast: bool,
+
+    /// Draw with nothing but ASCII characters, for terminals and fonts without braille
+    #[arg(long)]
+    pub ascii: bool,
+
+    /// Launch fireworks by themselves, like a screensaver
+    #[arg(long)]
+    pub auto: bool,
+
+    /// Shortest pause between automatic launches, in seconds [default: 0.3]
+    #[arg(long, value_name = "SECS", value_parser = positive)]
+    pub auto_min: Option<f64>,
+
+    /// Longest pause between automatic launches, in seconds [default: 2]
+    #[arg(long, value_name = "SECS", value_parser = positive)]
+    pub auto_max: Option<f64>,
+
+    /// Seconds the automatic show takes to build up to a finale [default: 30]
+    #[arg(long, value_name = "SECS", value_parser = positive)]
+    pub auto_ramp: Option<f64>,
+
+    /// Go round single rockets, shaped bursts, themes and a finale by itself, with captions, every scene lasting that many seconds
+    #[arg(long, value_name = "SECS", num_args = 0..=1, default_missing_value = "15", value_parser = positive, conflicts_with_all = ["auto", "show"])]
+    pub demo: Option<f64>,
+
+    /// Launch on the beat of raw 16-bit mono audio read from a file, a fifo or `-` for stdin, or with the audio feature and no path the default input device
+    #[cfg_attr(not(feature = "audio"), arg(long, value_name = "PATH", num_args = 1))]
+    #[cfg_attr(feature = "audio", arg(long, value_name = "PATH"))]
+    pub audio: Option<Option<PathBuf>>,
+
+    /// Sample rate of the --audio input read from a path
+    #[arg(long, value_name = "HZ", default_value_t = 44100, value_parser = clap::value_parser!(u32).range(1000..))]
+    pub audio_rate: u32,
+
+    /// Take commands such as `launch x=-0.25 color=red` (x from -0.5 at the left edge to 0.5 at the right) or `finale` from a fifo, a Unix socket made there or `-` for stdin
+    #[arg(long, value_name = "PATH")]
+    pub control: Option<PathBuf>,
+
+    /// Send every rocket to the instances that --join this address, such as 0.0.0.0:7878
+    #[arg(long, value_name = "ADDR")]
+    pub host: Option<String>,
+
+    /// Launch the rockets of the instance --host-ing at this address, such as example.com:7878
+    #[arg(long, value_name = "ADDR", conflicts_with = "host")]
+    pub join: Option<String>,
+
+    /// Play a whoosh for every launch and a bang for every burst through the audio output
+    #[cfg(feature = "sound")]
+    #[arg(long)]
+    pub sound: bool,
+
+    /// Loudness of --sound, from 0 to 1
+    #[cfg(feature = "sound")]
+    #[arg(long, value_name = "X", default_value_t = 0.5, value_parser = fraction)]
+    pub volume: f64,
+
+    /// Count down to a local time, HH:MM[:SS] or YYYY-MM-DD HH:MM[:SS], then set off a finale
+    #[arg(long, value_name = "TIME")]
+    pub countdown: Option<String>,
+
+    /// Count down to midnight, then set off a finale
+    #[arg(long, conflicts_with = "countdown")]
+    pub midnight: bool,
+
+    /// Play the launches of a show file, then quit
+    #[arg(long, value_name = "PATH")]
+    pub show: Option<PathBuf>,
+
+    /// Log every input to a file
+    #[arg(long, value_name = "PATH")]
+    pub record: Option<PathBuf>,
+
+    /// Play a logged session back instead of reading input
+    #[arg(long, value_name = "PATH")]
+    pub replay: Option<PathBuf>,
+
+    /// Save the show as an asciinema recording
+    #[arg(long, value_name = "PATH")]
+    pub cast: Option<PathBuf>,
+
+    /// Write what goes on to a file, since the terminal is taken by the show
+    #[arg(long, value_name = "PATH")]
+    pub log: Option<PathBuf>,
+
+    /// Log every launch, burst, tick and frame with its particle counts and timing, to --log or firewors.log
+    #[arg(long)]
+    pub debug: bool,
+
+    /// Write how the show went, its launches, bursts, sparks and how long it ran, to a JSON file when it ends
+    #[arg(long, value_name = "PATH")]
+    pub stats_out: Option<PathBuf>,
+
+    /// Quit by itself after that many seconds
+    #[arg(long, value_name = "SECS", value_parser = positive)]
+    pub cast_duration: Option<f64>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Run the simulation without a terminal and time it
+    Bench {
+        /// Simulation steps to time
+        #[arg(long, value_name = "N", default_value_t = 10000)]
+        ticks: u64,
+
+        /// Particles to keep alive while timing
+        #[arg(long, value_name = "N", default_value_t = 5000)]
+        particles: usize,
+    },
+    /// Run the show without a terminal and save it as an animated GIF or PNG
+    Export(Export),
+}
+
+#[derive(Debug, clap::Args)]
+pub struct Export {
+    /// Where to save it, a .gif, or a .png or .apng for an animated PNG
+    #[arg(short, long, value_name = "PATH")]
+    pub output: PathBuf,
+
+    /// How long the animation runs, in seconds (`15` or `15s`)
+    #[arg(long, value_name = "SECS", default_value = "15", value_parser = seconds)]
+    pub duration: f64,
+
+    /// Screen size to simulate, in terminal cells
+    #[arg(long, value_name = "CELLS", default_value_t = 80, value_parser = clap::value_parser!(u16).range(10..=1000))]
+    pub width: u16,
+
+    #[arg(long, value_name = "CELLS", default_value_t = 24, value_parser = clap::value_parser!(u16).range(10..=1000))]
+    pub height: u16,
+
+    /// Frames per second
+    #[arg(long, value_name = "N", default_value_t = 25, value_parser = clap::value_parser!(u16).range(1..=100))]
+    pub fps: u16,
+}
+
+impl Args {
+    /// Takes whatever wasn't given on the command line from `preset`.
+    pub fn fill_from(&mut self, preset: &Preset) {
+        self.tick_ms = self.tick_ms.or(preset.tick_ms);
+        self.gravity = self.gravity.or(preset.gravity);
+        self.drag = self.drag.or(preset.drag);
+        self.wind = self.wind.or(preset.wind);
+        self.turbulence = self.turbulence.or(preset.turbulence);
+        self.particles = self.particles.or(preset.particles);
+        self.subparticles = self.subparticles.or(preset.subparticles);
+        self.launch_speed = self.launch_speed.or(preset.launch_speed);
+        self.burst_scale = self.burst_scale.or(preset.burst_scale);
+        self.shell_sizes = self.shell_sizes.or(preset.shell_sizes);
+        self.apex_fuzz = self.apex_fuzz.or(preset.apex_fuzz);
+        self.depth = self.depth.or(preset.depth);
+        self.ground = self.ground.or(preset.ground);
+        self.wrap |= preset.wrap.unwrap_or(false);
+        self.auto |= preset.auto.unwrap_or(false);
+        self.auto_min = self.auto_min.or(preset.auto_min);
+        self.auto_max = self.auto_max.or(preset.auto_max);
+        self.auto_ramp = self.auto_ramp.or(preset.auto_ramp);
+    }
+}
+
+fn positive(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(x) if x > 0.0 && x.is_finite() => Ok(x),
+        _ => Err(format!("expected a positive number, got `{}`", value)),
+    }
+}
+
+fn seconds(value: &str) -> Result<f64, String> {
+    positive(value.strip_suffix('s').unwrap_or(value))
+}
+
+fn finite(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(x) if x.is_finite() => Ok(x),
+        _ => Err(format!("expected a number, got `{}`", value)),
+    }
+}
+
+#[cfg(feature = "sound")]
+fn fraction(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(x) if (0.0..=1.0).contains(&x) => Ok(x),
+        _ => Err(format!("expected a number from 0 to 1, got `{}`", value)),
+    }
+}
+
+fn sizes(value: &str) -> Result<[f64; 2], String> {
+    let sizes = value.split_once(',').and_then(|(min, max)| {
+        let (min, max) = (positive(min).ok()?, positive(max).ok()?);
+        (min <= max).then_some([min, max])
+    });
+    sizes.ok_or_else(|| {
+        format!(
+            "expected two positive numbers, the smallest first, like 0.5,2, got `{}`",
+            value
+        )
+    })
+}
+
+fn ground(value: &str) -> Result<Ground, String> {
+    Ground::from_name(value)
+        .ok_or_else(|| format!("expected through, bounce or slide, got `{}`", value))
+}
+
+fn non_negative(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(x) if x >= 0.0 && x.is_finite() => Ok(x),
+        _ => Err(format!("expected a number of at least 0, got `{}`", value)),
+    }
+}
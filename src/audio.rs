@@ -0,0 +1,267 @@
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{self, BufReader, Read},
+    path::Path,
+    sync::mpsc::{self, Receiver},
+    thread,
+    time::{Duration, Instant},
+};
+
+// samples in each stretch of sound whose loudness is compared, about 23ms
+// at 44.1kHz
+const WINDOW: usize = 1024;
+// a beat is this much louder than the last second or so on average
+const BEAT_RATIO: f32 = 1.4;
+// shortest time between two beats, in windows (about a quarter second)
+const REFRACTORY: usize = 10;
+// quieter than this is silence, whatever came before
+const SILENCE: f32 = 0.01;
+
+/// Beats picked out of raw 16-bit little-endian mono samples read from a
+/// file, a fifo or stdin (`-`) on a thread of their own, or with the `audio`
+/// feature out of the default input device. A file is read as fast as it
+/// would play, where a fifo or stdin set their own pace.
+pub struct Audio {
+    beats: Receiver<u8>,
+    // dropped along with the beats, which closes the input device
+    #[cfg(feature = "audio")]
+    _open: Option<mpsc::Sender<()>>,
+}
+
+impl Audio {
+    pub fn open(path: &Path, rate: u32) -> io::Result<Audio> {
+        let (input, paced): (Box<dyn Read + Send>, bool) = match path.to_str() {
+            Some("-") => (Box::new(io::stdin()), false),
+            _ => {
+                let file = File::open(path)?;
+                let paced = file.metadata()?.is_file();
+                (Box::new(file), paced)
+            }
+        };
+        let (tx, beats) = mpsc::channel();
+        thread::spawn(move || {
+            let mut input = BufReader::new(input);
+            let mut detector = BeatDetector::new(rate);
+            let mut bytes = vec![0; WINDOW * 2];
+            let start = Instant::now();
+            let mut windows = 0;
+            // ends with the sound, or once nobody listens any more
+            while input.read_exact(&mut bytes).is_ok() {
+                windows += 1;
+                if paced {
+                    let played =
+                        Duration::from_secs_f64((windows * WINDOW) as f64 / f64::from(rate));
+                    thread::sleep((start + played).saturating_duration_since(Instant::now()));
+                }
+                let sum: f32 = bytes
+                    .chunks_exact(2)
+                    .map(|b| (f32::from(i16::from_le_bytes([b[0], b[1]])) / 32768.0).powi(2))
+                    .sum();
+                let rms = (sum / WINDOW as f32).sqrt();
+                if let Some(strength) = detector.push(rms) {
+                    if tx.send(strength).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        Ok(Audio {
+            beats,
+            #[cfg(feature = "audio")]
+            _open: None,
+        })
+    }
+
+    /// Listens to the default input device, the microphone or whatever the
+    /// system has chosen to record from.
+    #[cfg(feature = "audio")]
+    pub fn listen() -> io::Result<Audio> {
+        let (tx, beats) = mpsc::channel();
+        let (_open, closed) = mpsc::channel::<()>();
+        let (opened, result) = mpsc::channel();
+        // the stream stays on the thread that opened it, which not every
+        // platform lets it leave
+        thread::spawn(move || match device::open(tx) {
+            Ok(_stream) => {
+                let _ = opened.send(Ok(()));
+                let _ = closed.recv();
+            }
+            Err(err) => {
+                let _ = opened.send(Err(err));
+            }
+        });
+        result
+            .recv()
+            .map_err(|_| io::Error::other("the audio input went away"))??;
+        Ok(Audio {
+            beats,
+            _open: Some(_open),
+        })
+    }
+
+    /// How strong each beat since the last call was, from 0 to 100.
+    pub fn beats(&self) -> Vec<u8> {
+        self.beats.try_iter().collect()
+    }
+}
+
+#[cfg(feature = "audio")]
+mod device {
+    use super::{BeatDetector, WINDOW};
+    use cpal::{
+        traits::{DeviceTrait, HostTrait, StreamTrait},
+        FromSample, SampleFormat, SizedSample, Stream, StreamConfig,
+    };
+    use std::{io, sync::mpsc::Sender};
+
+    pub(super) fn open(beats: Sender<u8>) -> io::Result<Stream> {
+        let device = cpal::default_host()
+            .default_input_device()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "there is no input device"))?;
+        let supported = device.default_input_config().map_err(io::Error::other)?;
+        let config = supported.config();
+        let stream = match supported.sample_format() {
+            SampleFormat::F32 => build::<f32>(&device, &config, beats),
+            SampleFormat::I16 => build::<i16>(&device, &config, beats),
+            SampleFormat::U16 => build::<u16>(&device, &config, beats),
+            SampleFormat::I32 => build::<i32>(&device, &config, beats),
+            format => {
+                return Err(io::Error::other(format!(
+                    "the input device records {format} samples"
+                )))
+            }
+        }
+        .map_err(io::Error::other)?;
+        stream.play().map_err(io::Error::other)?;
+        Ok(stream)
+    }
+
+    // the first channel of whatever comes in, a window at a time
+    fn build<T>(
+        device: &cpal::Device,
+        config: &StreamConfig,
+        beats: Sender<u8>,
+    ) -> Result<Stream, cpal::BuildStreamError>
+    where
+        T: SizedSample,
+        f32: FromSample<T>,
+    {
+        let channels = usize::from(config.channels).max(1);
+        let mut detector = BeatDetector::new(config.sample_rate);
+        let (mut sum, mut samples) = (0.0, 0);
+        device.build_input_stream(
+            config,
+            move |data: &[T], _: &_| {
+                for frame in data.chunks(channels) {
+                    sum += frame[0].to_sample::<f32>().powi(2);
+                    samples += 1;
+                    if samples < WINDOW {
+                        continue;
+                    }
+                    let rms = (sum / WINDOW as f32).sqrt();
+                    (sum, samples) = (0.0, 0);
+                    if let Some(strength) = detector.push(rms) {
+                        let _ = beats.send(strength);
+                    }
+                }
+            },
+            |_| {},
+            None,
+        )
+    }
+}
+
+// a beat is a window much louder than the ones just before it
+struct BeatDetector {
+    history: VecDeque<f32>,
+    // about a second's worth of windows
+    length: usize,
+    since_beat: usize,
+    // the loudest it has been lately, slowly forgotten
+    peak: f32,
+}
+
+impl BeatDetector {
+    fn new(rate: u32) -> BeatDetector {
+        let length = (rate as usize / WINDOW).max(4);
+        BeatDetector {
+            history: VecDeque::with_capacity(length),
+            length,
+            since_beat: REFRACTORY,
+            peak: SILENCE,
+        }
+    }
+
+    // takes the loudness of the next window, telling how strong a beat it
+    // was if it was one
+    fn push(&mut self, rms: f32) -> Option<u8> {
+        self.since_beat += 1;
+        self.peak = (self.peak * 0.999).max(rms).max(SILENCE);
+        let average = match self.history.len() {
+            0 => f32::INFINITY,
+            n => self.history.iter().sum::<f32>() / n as f32,
+        };
+        if self.history.len() == self.length {
+            self.history.pop_front();
+        }
+        self.history.push_back(rms);
+        let beat = rms > SILENCE && rms > BEAT_RATIO * average && self.since_beat >= REFRACTORY;
+        if !beat {
+            return None;
+        }
+        self.since_beat = 0;
+        Some((100.0 * rms / self.peak).round().min(100.0) as u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_out_the_kicks() {
+        let mut detector = BeatDetector::new(44100);
+        let mut beats = Vec::new();
+        for i in 0..400 {
+            // a soft hum with a kick every half second, the last ones quieter
+            let rms = match i % 21 {
+                0 if i < 300 => 0.8,
+                0 => 0.3,
+                _ => 0.05,
+            };
+            beats.extend(detector.push(rms));
+        }
+        // all but the first, which has nothing to compare to
+        assert_eq!(beats.len(), 400 / 21);
+        assert_eq!(beats[1], 100);
+        assert!(beats.last() < Some(&50));
+    }
+
+    #[test]
+    fn plays_a_file_in_time() {
+        let path = std::env::temp_dir().join(format!("firewors-{}.raw", std::process::id()));
+        // ten windows a second, with a kick in the fifth
+        let rate = 10 * WINDOW as u32;
+        let mut samples = Vec::new();
+        for window in 0..8 {
+            let level: i16 = if window == 4 { 26000 } else { 1600 };
+            for _ in 0..WINDOW {
+                samples.extend_from_slice(&level.to_le_bytes());
+            }
+        }
+        std::fs::write(&path, samples).unwrap();
+        let audio = Audio::open(&path, rate).unwrap();
+        thread::sleep(Duration::from_millis(200));
+        assert!(audio.beats().is_empty());
+        thread::sleep(Duration::from_millis(600));
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(audio.beats().len(), 1);
+    }
+
+    #[test]
+    fn silence_has_no_beats() {
+        let mut detector = BeatDetector::new(44100);
+        assert!((0..200).all(|i| detector.push(0.001 * (i % 3) as f32).is_none()));
+    }
+}
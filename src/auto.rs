@@ -0,0 +1,77 @@
+use crate::keymap::Action;
+use crate::pacing::Envelope;
+use crate::record::Input;
+use crate::schedule::Launch;
+use rand::{rngs::StdRng, Rng};
+
+// rockets in the biggest salvo before the show builds up to a multi-launch
+const MAX_SALVO: f64 = 4.0;
+// share of the usual launch speed rockets go up at in the quietest moments,
+// to burst lower and smaller
+const QUIET_SPEED: f64 = 0.75;
+
+/// Launches fireworks on its own, the pauses, the salvos and the bursts
+/// following an envelope until everything goes up at once, then starting
+/// over.
+pub struct AutoShow {
+    // seconds between salvos; a cycle starts out waiting up to `longest` and
+    // ends up waiting close to `shortest`
+    shortest: f64,
+    longest: f64,
+    // seconds from the first salvo of a cycle to its multi-launch, and how
+    // hard it goes in between
+    ramp: f64,
+    envelope: Envelope,
+    // how many times as often as that it launches
+    rate: f64,
+    tick: f64,
+    cycle_start: u64,
+    next_at: u64,
+}
+
+impl AutoShow {
+    pub fn new(min: f64, max: f64, ramp: f64, envelope: Envelope, tick: f64) -> AutoShow {
+        AutoShow {
+            shortest: min,
+            longest: max,
+            ramp,
+            envelope,
+            rate: 1.0,
+            tick,
+            cycle_start: 0,
+            next_at: 0,
+        }
+    }
+
+    /// Launches `rate` times as often as the pauses it was set up with.
+    pub fn set_rate(&mut self, rate: f64) {
+        self.rate = rate;
+    }
+
+    /// What to launch before step `tick`, if anything.
+    pub fn due(&mut self, tick: u64, rng: &mut StdRng) -> Vec<Input> {
+        if tick < self.next_at {
+            return Vec::new();
+        }
+        let progress = ((tick - self.cycle_start) as f64 * self.tick / self.ramp).min(1.0);
+        let (inputs, wait) = if progress >= 1.0 {
+            self.cycle_start = tick;
+            // let the finale burn out before starting quietly again
+            (vec![Input::Action(Action::MultiLaunch)], 2.0 * self.longest)
+        } else {
+            let intensity = self.envelope.at(progress);
+            let salvo = 1 + (intensity * MAX_SALVO) as usize;
+            let longest = self.longest - (self.longest - self.shortest) * intensity;
+            let wait = rng.gen_range(self.shortest..=longest);
+            let launch = Launch {
+                x: None,
+                color: None,
+                burst: None,
+                speed: Some(QUIET_SPEED + (1.0 - QUIET_SPEED) * intensity),
+            };
+            (vec![Input::Launch(launch); salvo], wait)
+        };
+        self.next_at = tick + (wait / self.rate / self.tick).round().max(1.0) as u64;
+        inputs
+    }
+}
@@ -0,0 +1,328 @@
+use crate::cast::Tee;
+use clap::ValueEnum;
+use crossterm::{
+    cursor,
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event},
+    execute,
+    terminal::{
+        self, disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+    },
+};
+use ratatui::{backend::CrosstermBackend, layout::Size, Frame, Terminal};
+use std::{
+    io::{self, Stdout},
+    panic,
+    time::Duration,
+};
+#[cfg(unix)]
+use {
+    crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind},
+    ratatui::backend::TermionBackend,
+    std::{
+        io::Write,
+        sync::mpsc::{self, Receiver, RecvTimeoutError},
+        thread,
+    },
+    termion::{
+        event::{self as termion_event, Key},
+        input::{MouseTerminal, TermRead},
+        raw::{IntoRawMode, RawTerminal},
+        screen::{AlternateScreen, IntoAlternateScreen},
+    },
+};
+
+/// The library that talks to the terminal.
+#[derive(Debug, Copy, Clone, PartialEq, ValueEnum)]
+pub enum BackendKind {
+    /// crossterm, on every platform
+    Crossterm,
+    /// termion, on Unix only
+    #[cfg(unix)]
+    Termion,
+}
+
+/// A terminal to draw on and read input from, put back the way it was when
+/// dropped. Input comes as crossterm's events whatever reads it.
+pub trait Term {
+    /// Draws a frame with `ui` and shows it.
+    fn draw(&mut self, ui: &mut dyn FnMut(&mut Frame)) -> io::Result<()>;
+
+    fn size(&self) -> io::Result<Size>;
+
+    /// The next input to come in within `timeout`, if any.
+    fn poll(&mut self, timeout: Duration) -> io::Result<Option<Event>>;
+}
+
+/// The size of the terminal in cells, before it is opened.
+pub fn size(kind: BackendKind) -> io::Result<(u16, u16)> {
+    match kind {
+        BackendKind::Crossterm => terminal::size(),
+        #[cfg(unix)]
+        BackendKind::Termion => termion::terminal_size(),
+    }
+}
+
+/// Takes over the terminal, `out` being where everything drawn goes.
+pub fn open(kind: BackendKind, out: Tee<Stdout>) -> io::Result<Box<dyn Term>> {
+    Ok(match kind {
+        BackendKind::Crossterm => Box::new(Crossterm::open(out)?),
+        #[cfg(unix)]
+        BackendKind::Termion => Box::new(Termion::open(out)?),
+    })
+}
+
+// raw mode, the alternate screen and the mouse for as long as it lives,
+// handed back to the shell however it goes: returning, `?` or a panic
+struct Crossterm {
+    terminal: Terminal<CrosstermBackend<Tee<Stdout>>>,
+}
+
+impl Crossterm {
+    fn open(out: Tee<Stdout>) -> io::Result<Crossterm> {
+        let terminal = Terminal::new(CrosstermBackend::new(out))?;
+        enable_raw_mode()?;
+        let mut term = Crossterm { terminal };
+        execute!(
+            term.terminal.backend_mut(),
+            EnterAlternateScreen,
+            EnableMouseCapture
+        )?;
+        // a panic message would be lost on the alternate screen, so the
+        // terminal is put back before it is printed
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            Crossterm::leave();
+            hook(info);
+        }));
+        Ok(term)
+    }
+
+    fn leave() {
+        let _ = disable_raw_mode();
+        let _ = execute!(
+            io::stdout(),
+            LeaveAlternateScreen,
+            DisableMouseCapture,
+            cursor::Show
+        );
+    }
+}
+
+impl Term for Crossterm {
+    fn draw(&mut self, ui: &mut dyn FnMut(&mut Frame)) -> io::Result<()> {
+        self.terminal.draw(ui).map(drop)
+    }
+
+    fn size(&self) -> io::Result<Size> {
+        self.terminal.size()
+    }
+
+    fn poll(&mut self, timeout: Duration) -> io::Result<Option<Event>> {
+        if event::poll(timeout)? {
+            event::read().map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+impl Drop for Crossterm {
+    fn drop(&mut self) {
+        Crossterm::leave();
+    }
+}
+
+// the same with termion, whose screen and mouse modes go with the writer
+// they wrap and whose raw mode goes with stdout's, dropped in that order
+#[cfg(unix)]
+struct Termion {
+    terminal: Terminal<TermionBackend<MouseTerminal<AlternateScreen<Tee<Stdout>>>>>,
+    _raw: RawTerminal<Stdout>,
+    // termion only reads blocking, so a thread waits on the keyboard
+    events: Receiver<io::Result<termion_event::Event>>,
+}
+
+#[cfg(unix)]
+impl Termion {
+    fn open(out: Tee<Stdout>) -> io::Result<Termion> {
+        let raw = io::stdout().into_raw_mode()?;
+        let out = MouseTerminal::from(out.into_alternate_screen()?);
+        let terminal = Terminal::new(TermionBackend::new(out))?;
+        let (tx, events) = mpsc::channel();
+        thread::spawn(move || {
+            for event in io::stdin().events() {
+                if tx.send(event).is_err() {
+                    break;
+                }
+            }
+        });
+        // raw mode comes back off while unwinding, after the message
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            let mut out = io::stdout();
+            let _ = write!(
+                out,
+                "{}{}",
+                termion::screen::ToMainScreen,
+                termion::cursor::Show
+            );
+            let _ = out.flush();
+            hook(info);
+        }));
+        Ok(Termion {
+            terminal,
+            _raw: raw,
+            events,
+        })
+    }
+}
+
+#[cfg(unix)]
+impl Term for Termion {
+    fn draw(&mut self, ui: &mut dyn FnMut(&mut Frame)) -> io::Result<()> {
+        self.terminal.draw(ui).map(drop)
+    }
+
+    fn size(&self) -> io::Result<Size> {
+        self.terminal.size()
+    }
+
+    fn poll(&mut self, timeout: Duration) -> io::Result<Option<Event>> {
+        let event = match self.events.recv_timeout(timeout) {
+            Ok(event) => event?,
+            Err(RecvTimeoutError::Timeout) => return Ok(None),
+            Err(RecvTimeoutError::Disconnected) => return Err(io::ErrorKind::UnexpectedEof.into()),
+        };
+        // one that crossterm has no word for is as good as none
+        Ok(translate(event))
+    }
+}
+
+// a termion event as crossterm would have seen it
+#[cfg(unix)]
+fn translate(event: termion_event::Event) -> Option<Event> {
+    match event {
+        termion_event::Event::Key(key) => {
+            let (code, modifiers) = translate_key(key)?;
+            Some(Event::Key(KeyEvent::new(code, modifiers)))
+        }
+        termion_event::Event::Mouse(mouse) => {
+            let (kind, column, row) = match mouse {
+                termion_event::MouseEvent::Press(button, column, row) => {
+                    let kind = match button {
+                        termion_event::MouseButton::Left => MouseEventKind::Down(MouseButton::Left),
+                        termion_event::MouseButton::Right => {
+                            MouseEventKind::Down(MouseButton::Right)
+                        }
+                        termion_event::MouseButton::Middle => {
+                            MouseEventKind::Down(MouseButton::Middle)
+                        }
+                        termion_event::MouseButton::WheelUp => MouseEventKind::ScrollUp,
+                        termion_event::MouseButton::WheelDown => MouseEventKind::ScrollDown,
+                        termion_event::MouseButton::WheelLeft => MouseEventKind::ScrollLeft,
+                        termion_event::MouseButton::WheelRight => MouseEventKind::ScrollRight,
+                    };
+                    (kind, column, row)
+                }
+                // termion doesn't say which button these are for; the right
+                // one is the only one firewors follows held down
+                termion_event::MouseEvent::Release(column, row) => {
+                    (MouseEventKind::Up(MouseButton::Right), column, row)
+                }
+                termion_event::MouseEvent::Hold(column, row) => {
+                    (MouseEventKind::Drag(MouseButton::Right), column, row)
+                }
+            };
+            // termion counts cells from 1
+            Some(Event::Mouse(MouseEvent {
+                kind,
+                column: column.saturating_sub(1),
+                row: row.saturating_sub(1),
+                modifiers: KeyModifiers::NONE,
+            }))
+        }
+        termion_event::Event::Unsupported(_) => None,
+    }
+}
+
+#[cfg(unix)]
+fn translate_key(key: Key) -> Option<(KeyCode, KeyModifiers)> {
+    let (shift, alt, ctrl) = (
+        KeyModifiers::SHIFT,
+        KeyModifiers::ALT,
+        KeyModifiers::CONTROL,
+    );
+    let none = KeyModifiers::NONE;
+    Some(match key {
+        Key::Char('\n') => (KeyCode::Enter, none),
+        Key::Char('\t') => (KeyCode::Tab, none),
+        Key::Char(c) => (KeyCode::Char(c), none),
+        Key::Alt(c) => (KeyCode::Char(c), alt),
+        Key::Ctrl(c) => (KeyCode::Char(c), ctrl),
+        Key::Backspace => (KeyCode::Backspace, none),
+        Key::Left => (KeyCode::Left, none),
+        Key::ShiftLeft => (KeyCode::Left, shift),
+        Key::AltLeft => (KeyCode::Left, alt),
+        Key::CtrlLeft => (KeyCode::Left, ctrl),
+        Key::Right => (KeyCode::Right, none),
+        Key::ShiftRight => (KeyCode::Right, shift),
+        Key::AltRight => (KeyCode::Right, alt),
+        Key::CtrlRight => (KeyCode::Right, ctrl),
+        Key::Up => (KeyCode::Up, none),
+        Key::ShiftUp => (KeyCode::Up, shift),
+        Key::AltUp => (KeyCode::Up, alt),
+        Key::CtrlUp => (KeyCode::Up, ctrl),
+        Key::Down => (KeyCode::Down, none),
+        Key::ShiftDown => (KeyCode::Down, shift),
+        Key::AltDown => (KeyCode::Down, alt),
+        Key::CtrlDown => (KeyCode::Down, ctrl),
+        Key::Home => (KeyCode::Home, none),
+        Key::CtrlHome => (KeyCode::Home, ctrl),
+        Key::End => (KeyCode::End, none),
+        Key::CtrlEnd => (KeyCode::End, ctrl),
+        Key::PageUp => (KeyCode::PageUp, none),
+        Key::PageDown => (KeyCode::PageDown, none),
+        Key::BackTab => (KeyCode::BackTab, shift),
+        Key::Delete => (KeyCode::Delete, none),
+        Key::Insert => (KeyCode::Insert, none),
+        Key::F(n) => (KeyCode::F(n), none),
+        Key::Esc => (KeyCode::Esc, none),
+        _ => return None,
+    })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn termion_input_reads_like_crossterms() {
+        let key = |key| match translate(termion_event::Event::Key(key)) {
+            Some(Event::Key(event)) => Some((event.code, event.modifiers)),
+            _ => None,
+        };
+        assert_eq!(
+            key(Key::Char('q')),
+            Some((KeyCode::Char('q'), KeyModifiers::NONE))
+        );
+        assert_eq!(
+            key(Key::Char('\n')),
+            Some((KeyCode::Enter, KeyModifiers::NONE))
+        );
+        assert_eq!(
+            key(Key::Ctrl('c')),
+            Some((KeyCode::Char('c'), KeyModifiers::CONTROL))
+        );
+        assert_eq!(key(Key::Null), None);
+        let click = termion_event::MouseEvent::Press(termion_event::MouseButton::Left, 1, 5);
+        assert_eq!(
+            translate(termion_event::Event::Mouse(click)),
+            Some(Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Down(MouseButton::Left),
+                column: 0,
+                row: 4,
+                modifiers: KeyModifiers::NONE,
+            }))
+        );
+    }
+}
@@ -0,0 +1,74 @@
+use crate::App;
+use firewors::sim::{Vector, REFERENCE_DT};
+use std::time::{Duration, Instant};
+
+// a roomy terminal, so the numbers don't depend on the one you run it in
+const WIDTH: f64 = 200.0;
+const HEIGHT: f64 = 60.0;
+
+// embers a burst is guessed to add when topping up the particle count
+const BURST_GUESS: usize = 50;
+
+/// Times `ticks` simulation steps while keeping about `particles` alive,
+/// setting off new bursts between steps whenever the count drops below it.
+pub fn run(app: &mut App, ticks: u64, particles: usize) {
+    // leave room for the asked for count even if it's all one color
+    app.sim.set_max_particles(particles.max(1));
+    app.sim.set_floor(-HEIGHT / 2.0);
+    let mut launched = 0;
+    // get up to speed before the clock starts
+    for _ in 0..200 {
+        top_up(app, particles, &mut launched);
+        app.sim.step(REFERENCE_DT);
+    }
+
+    let mut times = Vec::with_capacity(ticks as usize);
+    let mut alive = 0;
+    for _ in 0..ticks {
+        top_up(app, particles, &mut launched);
+        let start = Instant::now();
+        app.sim.step(REFERENCE_DT);
+        times.push(start.elapsed());
+        alive += app.sim.particle_count();
+    }
+    if times.is_empty() {
+        println!("0 ticks");
+        return;
+    }
+
+    let total: Duration = times.iter().sum();
+    times.sort();
+    let percentile = |p: usize| times[(times.len() - 1) * p / 100];
+    println!("{} ticks in {:?}", ticks, total);
+    println!("{:.0} ticks/sec", ticks as f64 / total.as_secs_f64());
+    println!(
+        "per tick: p50 {:?}  p90 {:?}  p99 {:?}  max {:?}",
+        percentile(50),
+        percentile(90),
+        percentile(99),
+        percentile(100)
+    );
+    println!(
+        "{} particles alive on average, {} bursts set off",
+        alive / times.len(),
+        launched
+    );
+}
+
+// rockets going down at full speed are past their apex, so they burst on the
+// next step, full size, and the count catches up straight away
+fn top_up(app: &mut App, particles: usize, launched: &mut usize) {
+    let short = particles.saturating_sub(app.sim.particle_count());
+    if short == 0 {
+        return;
+    }
+    let speed = 8.0 * HEIGHT.sqrt();
+    let colors = app.sim.palette().len();
+    for _ in 0..short.div_ceil(BURST_GUESS) {
+        let x = (*launched % 20) as f64 * WIDTH / 20.0 - WIDTH / 2.0;
+        let pos = Vector { x, y: HEIGHT / 4.0 };
+        let vel = Vector { x: 0.0, y: -speed };
+        app.sim.launch(*launched % colors, pos, vel);
+        *launched += 1;
+    }
+}
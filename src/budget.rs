@@ -0,0 +1,87 @@
+use ratatui::layout::Rect;
+use std::time::Duration;
+
+// the screen the show was tuned on, in cells; bigger ones get up to
+// MAX_ROOM times as much of everything
+const REFERENCE_CELLS: f64 = 100.0 * 30.0;
+const MAX_ROOM: f64 = 3.0;
+// seconds a frame may take to put together and send to the terminal, and
+// the share of that under which the show builds back up
+const FRAME_BUDGET: f64 = 0.016;
+const RECOVER_UNDER: f64 = 0.6;
+// how much a slow or a quick frame backs the load off or builds it up, and
+// the least it backs off to
+const BACK_OFF: f64 = 0.97;
+const BUILD_UP: f64 = 1.005;
+const MIN_LOAD: f64 = 0.25;
+// share of every frame's time the smoothed one moves by
+const SMOOTHING: f64 = 0.1;
+
+/// How much the show takes on: more particles, denser bursts and quicker
+/// launches on a screen bigger than usual, all of it backing off while the
+/// terminal takes too long to draw a frame.
+pub struct Budget {
+    // whether it goes by the screen size and by how long frames take
+    screen: bool,
+    frames: bool,
+    // how much more the screen holds than REFERENCE_CELLS, at least 1
+    room: f64,
+    // the share of that the terminal keeps up with
+    load: f64,
+    frame_time: f64,
+}
+
+impl Budget {
+    pub fn new(screen: bool, frames: bool) -> Budget {
+        Budget {
+            screen,
+            frames,
+            room: 1.0,
+            load: 1.0,
+            frame_time: 0.0,
+        }
+    }
+
+    /// Fits it to a screen of `size` cells.
+    pub fn resize(&mut self, size: Rect) {
+        if !self.screen {
+            return;
+        }
+        let cells = f64::from(size.width) * f64::from(size.height);
+        self.room = (cells / REFERENCE_CELLS).sqrt().clamp(1.0, MAX_ROOM);
+    }
+
+    /// Takes a frame that `took` that long, telling whether the load moved.
+    pub fn frame(&mut self, took: Duration) -> bool {
+        if !self.frames {
+            return false;
+        }
+        self.frame_time += (took.as_secs_f64() - self.frame_time) * SMOOTHING;
+        let load = if self.frame_time > FRAME_BUDGET {
+            (self.load * BACK_OFF).max(MIN_LOAD)
+        } else if self.frame_time < FRAME_BUDGET * RECOVER_UNDER {
+            (self.load * BUILD_UP).min(1.0)
+        } else {
+            self.load
+        };
+        let moved = load != self.load;
+        self.load = load;
+        moved
+    }
+
+    /// How many particles can be alive at once, `usual` being how many on
+    /// the reference screen at full load.
+    pub fn particles(&self, usual: usize) -> usize {
+        (usual as f64 * self.scale()).round() as usize
+    }
+
+    /// How much denser bursts are, how much more often the auto show
+    /// launches and how many more particles it holds than usual.
+    pub fn scale(&self) -> f64 {
+        self.room * self.load
+    }
+
+    pub fn load(&self) -> f64 {
+        self.load
+    }
+}
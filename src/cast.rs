@@ -0,0 +1,107 @@
+use crate::backend::{self, BackendKind};
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// An asciicast v2 recording: a JSON header line, then one
+/// `[seconds, "o", data]` line for every chunk of output the terminal got,
+/// and a `[seconds, "r", "WxH"]` one whenever the terminal was resized.
+pub struct Cast {
+    out: BufWriter<File>,
+    start: Instant,
+    pending: Vec<u8>,
+    backend: BackendKind,
+    size: (u16, u16),
+}
+
+impl Cast {
+    /// Starts one for a terminal `backend` talks to, `size` cells large.
+    pub fn create(path: &Path, backend: BackendKind, size: (u16, u16)) -> io::Result<Cast> {
+        let (width, height) = size;
+        let mut out = BufWriter::new(File::create(path)?);
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        writeln!(
+            out,
+            r#"{{"version": 2, "width": {}, "height": {}, "timestamp": {}, "env": {{"TERM": "xterm-256color"}}}}"#,
+            width, height, timestamp
+        )?;
+        Ok(Cast {
+            out,
+            start: Instant::now(),
+            pending: Vec::new(),
+            backend,
+            size,
+        })
+    }
+
+    // everything written since the last flush is one frame
+    fn frame(&mut self) -> io::Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let time = self.start.elapsed().as_secs_f64();
+        // a frame drawn for a new size comes after the resize, so players
+        // lay it out on a screen that fits
+        if let Ok(size) = backend::size(self.backend) {
+            if size != self.size {
+                self.size = size;
+                writeln!(self.out, "[{:.6}, \"r\", \"{}x{}\"]", time, size.0, size.1)?;
+            }
+        }
+        let data = String::from_utf8_lossy(&self.pending).into_owned();
+        writeln!(self.out, "[{:.6}, \"o\", \"{}\"]", time, escape(&data))?;
+        self.pending.clear();
+        self.out.flush()
+    }
+}
+
+fn escape(data: &str) -> String {
+    let mut escaped = String::with_capacity(data.len());
+    for c in data.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Passes output through to the terminal, copying it into a cast if there is
+/// one.
+pub struct Tee<W: Write> {
+    inner: W,
+    cast: Option<Cast>,
+}
+
+impl<W: Write> Tee<W> {
+    pub fn new(inner: W, cast: Option<Cast>) -> Tee<W> {
+        Tee { inner, cast }
+    }
+}
+
+impl<W: Write> Write for Tee<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        if let Some(cast) = self.cast.as_mut() {
+            cast.pending.extend_from_slice(&buf[..n]);
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()?;
+        match self.cast.as_mut() {
+            Some(cast) => cast.frame(),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,571 @@
+use crate::ansi::Depth;
+use crate::error::Error;
+use crate::keymap::{parse_key, Action, Keymap};
+use crate::pacing::Envelope;
+use crate::paint::{Glyph, Glyphs};
+use crate::scenery::Layers;
+use crate::trigger::Trigger;
+use firewors::sim::{Ground, Vector, Well, DEFAULT_PALETTE, MAX_DEPTH};
+use ratatui::style::Color;
+use serde::{Deserialize, Deserializer};
+use std::{
+    collections::HashMap,
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+pub const CONFIG_FILE: &str = "firewo.toml";
+
+/// The palettes `--theme` knows without a config file.
+pub const THEMES: [(&str, &[&str]); 8] = [
+    (
+        "classic",
+        &["blue", "green", "magenta", "red", "yellow", "white"],
+    ),
+    (
+        "pastel",
+        &[
+            "#ffb3ba", "#ffdfba", "#ffffba", "#baffc9", "#bae1ff", "#e0c3fc",
+        ],
+    ),
+    (
+        "neon",
+        &[
+            "#ff10f0", "#00f0ff", "#39ff14", "#fff01f", "#ff3131", "#bc13fe",
+        ],
+    ),
+    ("mono", &["white"]),
+    // what --high-contrast picks unless told otherwise
+    ("contrast", &["white", "yellow", "cyan", "lightmagenta"]),
+    (
+        "pride",
+        &[
+            "#e40303", "#ff8c00", "#ffed00", "#008026", "#004dff", "#750787",
+        ],
+    ),
+    // told apart without telling red from green, from the Okabe-Ito colors
+    (
+        "deuteranopia",
+        &[
+            "#0072b2", "#e69f00", "#56b4e9", "#f0e442", "#cc79a7", "#ffffff",
+        ],
+    ),
+    // the same, leaving out the reds that look dark without red cones
+    (
+        "protanopia",
+        &["#0072b2", "#56b4e9", "#f0e442", "#ffb000", "#ffffff"],
+    ),
+];
+
+// what the themes that the closest of the 16 colors would muddle are drawn
+// with on a terminal that only has those
+const ANSI_THEMES: [(&str, &[&str]); 5] = [
+    (
+        "pastel",
+        &[
+            "lightred",
+            "lightyellow",
+            "lightgreen",
+            "lightcyan",
+            "lightblue",
+            "lightmagenta",
+        ],
+    ),
+    (
+        "neon",
+        &[
+            "lightmagenta",
+            "lightcyan",
+            "lightgreen",
+            "lightyellow",
+            "lightred",
+            "magenta",
+        ],
+    ),
+    (
+        "pride",
+        &["red", "lightred", "yellow", "green", "blue", "magenta"],
+    ),
+    (
+        "deuteranopia",
+        &["lightblue", "yellow", "cyan", "white", "lightmagenta"],
+    ),
+    (
+        "protanopia",
+        &["blue", "lightcyan", "lightyellow", "yellow", "white"],
+    ),
+];
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    palette: Option<Vec<String>>,
+    theme: HashMap<String, Vec<String>>,
+    keys: HashMap<String, Keys>,
+    scenery: Layers,
+    preset: HashMap<String, PresetFile>,
+    trigger: Vec<TriggerFile>,
+    glyphs: GlyphChoice,
+    pacing: PacingFile,
+    well: Vec<WellFile>,
+}
+
+// a key for an action, or a list of them
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Keys {
+    One(String),
+    Many(Vec<String>),
+}
+
+// a `[[trigger]]` table
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TriggerFile {
+    at: Option<String>,
+    every: Option<String>,
+    fire: String,
+}
+
+// a `[[well]]` table, placed as a share of the screen from its middle
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WellFile {
+    x: f64,
+    y: f64,
+    #[serde(default = "well_strength")]
+    strength: f64,
+    #[serde(default)]
+    spin: f64,
+    #[serde(default = "well_radius")]
+    radius: f64,
+}
+
+fn well_strength() -> f64 {
+    60.0
+}
+
+fn well_radius() -> f64 {
+    15.0
+}
+
+impl WellFile {
+    fn check(&self) -> Result<Well, String> {
+        if !(-0.5..=0.5).contains(&self.x) || !(-0.5..=0.5).contains(&self.y) {
+            return Err("x and y need to be between -0.5 and 0.5".into());
+        }
+        if !(self.strength.is_finite() && self.spin.is_finite()) {
+            return Err("strength and spin need to be numbers".into());
+        }
+        if !(self.radius > 0.0 && self.radius.is_finite()) {
+            return Err("radius needs to be positive".into());
+        }
+        Ok(Well {
+            pos: Vector {
+                x: self.x,
+                y: self.y,
+            },
+            strength: self.strength,
+            spin: self.spin,
+            radius: self.radius,
+        })
+    }
+}
+
+// the `[pacing]` table
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct PacingFile {
+    envelope: Option<Vec<f64>>,
+    duration: Option<f64>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct PresetFile {
+    palette: Option<Vec<String>>,
+    theme: Option<String>,
+    #[serde(flatten)]
+    settings: Preset,
+}
+
+/// Settings a preset can give in place of the command line defaults.
+#[derive(Deserialize, Default, Debug, Clone, Copy)]
+pub struct Preset {
+    pub tick_ms: Option<u64>,
+    pub gravity: Option<f64>,
+    pub drag: Option<f64>,
+    pub wind: Option<f64>,
+    pub turbulence: Option<f64>,
+    pub particles: Option<u64>,
+    pub subparticles: Option<u64>,
+    pub launch_speed: Option<f64>,
+    pub burst_scale: Option<f64>,
+    pub shell_sizes: Option<[f64; 2]>,
+    pub apex_fuzz: Option<f64>,
+    pub depth: Option<u8>,
+    #[serde(default, deserialize_with = "ground")]
+    pub ground: Option<Ground>,
+    pub wrap: Option<bool>,
+    pub auto: Option<bool>,
+    pub auto_min: Option<f64>,
+    pub auto_max: Option<f64>,
+    pub auto_ramp: Option<f64>,
+}
+
+impl Preset {
+    pub fn check(&self) -> Result<(), String> {
+        if self.tick_ms == Some(0) || self.particles == Some(0) || self.subparticles == Some(0) {
+            return Err("tick_ms, particles and subparticles need to be at least 1".into());
+        }
+        if self
+            .depth
+            .is_some_and(|depth| !(1..=MAX_DEPTH).contains(&depth))
+        {
+            return Err(format!("depth needs to be between 1 and {}", MAX_DEPTH));
+        }
+        if self.gravity.is_some_and(|g| !(g >= 0.0 && g.is_finite())) {
+            return Err("gravity can't be negative".into());
+        }
+        if self.drag.is_some_and(|d| !(d >= 0.0 && d.is_finite())) {
+            return Err("drag can't be negative".into());
+        }
+        if self
+            .turbulence
+            .is_some_and(|t| !(t >= 0.0 && t.is_finite()))
+        {
+            return Err("turbulence can't be negative".into());
+        }
+        if self.apex_fuzz.is_some_and(|f| !(f >= 0.0 && f.is_finite())) {
+            return Err("apex_fuzz can't be negative".into());
+        }
+        if self
+            .shell_sizes
+            .is_some_and(|[min, max]| !(min > 0.0 && min <= max && max.is_finite()))
+        {
+            return Err("shell_sizes needs to be two positive numbers, the smallest first".into());
+        }
+        if self.wind.is_some_and(|w| !w.is_finite()) {
+            return Err("wind needs to be a number".into());
+        }
+        let positive = [
+            ("launch_speed", self.launch_speed),
+            ("burst_scale", self.burst_scale),
+            ("auto_min", self.auto_min),
+            ("auto_max", self.auto_max),
+            ("auto_ramp", self.auto_ramp),
+        ];
+        for (name, x) in positive {
+            if x.is_some_and(|x| !(x > 0.0 && x.is_finite())) {
+                return Err(format!("{} needs to be positive", name));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What the `[glyphs]` table draws each kind of particle with, in place
+/// of the defaults.
+#[derive(Deserialize, Default, Debug, Clone, Copy)]
+#[serde(default, deny_unknown_fields)]
+pub struct GlyphChoice {
+    #[serde(deserialize_with = "glyph")]
+    rocket: Option<Glyph>,
+    #[serde(deserialize_with = "glyph")]
+    spark: Option<Glyph>,
+    #[serde(deserialize_with = "glyph")]
+    trail: Option<Glyph>,
+    #[serde(deserialize_with = "glyph")]
+    smoke: Option<Glyph>,
+}
+
+impl GlyphChoice {
+    /// `glyphs` with what was picked put in.
+    pub fn over(&self, glyphs: Glyphs) -> Glyphs {
+        Glyphs {
+            rocket: self.rocket.unwrap_or(glyphs.rocket),
+            spark: self.spark.unwrap_or(glyphs.spark),
+            trail: self.trail.unwrap_or(glyphs.trail),
+            smoke: self.smoke.unwrap_or(glyphs.smoke),
+        }
+    }
+}
+
+fn glyph<'de, D: Deserializer<'de>>(d: D) -> Result<Option<Glyph>, D::Error> {
+    let name = String::deserialize(d)?;
+    match Glyph::parse(&name) {
+        Some(glyph) => Ok(Some(glyph)),
+        None => Err(serde::de::Error::custom(format!(
+            "a glyph needs to be braille, block, dot or a single character, not `{}`",
+            name
+        ))),
+    }
+}
+
+// a ground mode by its name, as --ground takes it
+fn ground<'de, D: Deserializer<'de>>(d: D) -> Result<Option<Ground>, D::Error> {
+    let name = String::deserialize(d)?;
+    match Ground::from_name(&name) {
+        Some(ground) => Ok(Some(ground)),
+        None => Err(serde::de::Error::custom(format!(
+            "ground needs to be through, bounce or slide, not `{}`",
+            name
+        ))),
+    }
+}
+
+pub struct Config {
+    pub palette: Vec<Color>,
+    pub keymap: Keymap,
+    pub preset: Preset,
+    pub scenery: Layers,
+    pub triggers: Vec<Trigger>,
+    pub glyphs: GlyphChoice,
+    pub pacing: Envelope,
+    /// Where on the screen they go is a share of its width and height from
+    /// the middle, until the screen size is known.
+    pub wells: Vec<Well>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            palette: DEFAULT_PALETTE.to_vec(),
+            keymap: Keymap::new(DEFAULT_PALETTE.len()),
+            preset: Preset::default(),
+            scenery: Layers::default(),
+            triggers: Vec::new(),
+            glyphs: GlyphChoice::default(),
+            pacing: Envelope::default(),
+            wells: Vec::new(),
+        }
+    }
+}
+
+/// `firewo.toml` in the current directory if there is one, otherwise
+/// `firewo-rs/config.toml` in the user's config directory.
+pub fn config_path() -> PathBuf {
+    let local = PathBuf::from(CONFIG_FILE);
+    if local.exists() {
+        return local;
+    }
+    let config_dir = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => match env::var_os("HOME") {
+            Some(home) => PathBuf::from(home).join(".config"),
+            None => return local,
+        },
+    };
+    config_dir.join("firewo-rs").join("config.toml")
+}
+
+impl Config {
+    /// Reads `path`, falling back to the defaults when it doesn't exist, and
+    /// picks out `preset` and `theme` if they are asked for, a built-in
+    /// theme in the colors the terminal's `depth` tells apart.
+    pub fn load(
+        path: &Path,
+        preset: Option<&str>,
+        theme: Option<&str>,
+        depth: Depth,
+    ) -> Result<Config, Error> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => Some(text),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => return Err(Error::open(path.display())(err)),
+        };
+        Config::parse(path, text.as_deref(), preset, theme, depth).map_err(Error::Config)
+    }
+
+    fn parse(
+        path: &Path,
+        text: Option<&str>,
+        preset: Option<&str>,
+        theme: Option<&str>,
+        depth: Depth,
+    ) -> Result<Config, String> {
+        let mut file: ConfigFile = match text {
+            Some(text) => {
+                toml::from_str(text).map_err(|err| format!("{}: {}", path.display(), err))?
+            }
+            None => match preset {
+                Some(name) => {
+                    return Err(format!(
+                        "no preset `{}`, {} doesn't exist",
+                        name,
+                        path.display()
+                    ))
+                }
+                None => ConfigFile::default(),
+            },
+        };
+
+        let mut settings = Preset::default();
+        let mut theme = theme.map(str::to_string);
+        if let Some(name) = preset {
+            let preset = file.preset.remove(name).ok_or(format!(
+                "no preset `{}` in {}",
+                name,
+                path.display()
+            ))?;
+            preset
+                .settings
+                .check()
+                .map_err(|err| format!("preset `{}`: {}", name, err))?;
+            if preset.palette.is_some() {
+                file.palette = preset.palette;
+            }
+            // the command line still wins
+            theme = theme.or(preset.theme);
+            settings = preset.settings;
+        }
+        if let Some(duration) = file.pacing.duration {
+            if !(duration > 0.0 && duration.is_finite()) {
+                return Err("pacing: duration needs to be positive".into());
+            }
+            // a preset's auto_ramp is the same thing, and wins
+            settings.auto_ramp = settings.auto_ramp.or(Some(duration));
+        }
+        if let Some(name) = theme {
+            file.palette = Some(match file.theme.remove(&name) {
+                Some(names) => names,
+                None => built_in_theme(&name, depth).ok_or_else(|| unknown_theme(&name, &file))?,
+            });
+        }
+
+        let palette = match file.palette {
+            Some(names) => names
+                .iter()
+                .map(|name| parse_color(name).ok_or(format!("unknown color `{}`", name)))
+                .collect::<Result<Vec<_>, _>>()?,
+            None => DEFAULT_PALETTE.to_vec(),
+        };
+        if palette.is_empty() {
+            return Err("the palette needs at least one color".into());
+        }
+
+        let mut keymap = Keymap::new(palette.len());
+        for (name, keys) in file.keys.iter() {
+            let action = Action::from_name(name).ok_or(format!("unknown action `{}`", name))?;
+            let keys = match keys {
+                Keys::One(key) => std::slice::from_ref(key),
+                Keys::Many(keys) => keys.as_slice(),
+            };
+            let keys = keys
+                .iter()
+                .map(|key| parse_key(key).ok_or(format!("unknown key `{}`", key)))
+                .collect::<Result<Vec<_>, _>>()?;
+            keymap.bind(action, &keys);
+        }
+
+        let triggers = file
+            .trigger
+            .iter()
+            .enumerate()
+            .map(|(i, t)| {
+                Trigger::parse(t.at.as_deref(), t.every.as_deref(), &t.fire)
+                    .map_err(|err| format!("trigger {}: {}", i + 1, err))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let wells = file
+            .well
+            .iter()
+            .enumerate()
+            .map(|(i, w)| w.check().map_err(|err| format!("well {}: {}", i + 1, err)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let pacing = match file.pacing.envelope {
+            Some(points) => Envelope::new(points).map_err(|err| format!("pacing: {}", err))?,
+            None => Envelope::default(),
+        };
+
+        Ok(Config {
+            palette,
+            keymap,
+            preset: settings,
+            scenery: file.scenery,
+            triggers,
+            glyphs: file.glyphs,
+            pacing,
+            wells,
+        })
+    }
+}
+
+/// The colors of one of THEMES, or of its stand-in from the 16 colors if
+/// that is all the terminal has.
+pub fn built_in_theme(name: &str, depth: Depth) -> Option<Vec<String>> {
+    let stand_in = match depth {
+        Depth::Ansi => ANSI_THEMES.iter().find(|(n, _)| *n == name),
+        Depth::Indexed | Depth::Truecolor => None,
+    };
+    let (_, names) = stand_in.or_else(|| THEMES.iter().find(|(n, _)| *n == name))?;
+    Some(names.iter().map(|name| name.to_string()).collect())
+}
+
+fn unknown_theme(name: &str, file: &ConfigFile) -> String {
+    let mut known: Vec<&str> = THEMES.iter().map(|(n, _)| *n).collect();
+    let mut own: Vec<&str> = file.theme.keys().map(String::as_str).collect();
+    own.sort_unstable();
+    known.extend(own);
+    format!("no theme `{}`, there are {}", name, known.join(", "))
+}
+
+/// Parses a color name as used by the terminal (`red`, `lightblue`...) or a
+/// `#rrggbb` hex code.
+pub fn parse_color(name: &str) -> Option<Color> {
+    if let Some(hex) = name.strip_prefix('#') {
+        if hex.len() != 6 {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+        return Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?));
+    }
+    let color = match name.to_lowercase().as_str() {
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" => Color::Gray,
+        "darkgray" => Color::DarkGray,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "white" => Color::White,
+        _ => return None,
+    };
+    Some(color)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn preset(text: &str) -> Result<Preset, String> {
+        let path = Path::new("firewors.toml");
+        let config = Config::parse(path, Some(text), Some("night"), None, Depth::Truecolor)?;
+        Ok(config.preset)
+    }
+
+    #[test]
+    fn presets_leave_out_what_they_like() {
+        let night = preset("[preset.night]\ngravity = 25.0\n").unwrap();
+        assert_eq!(night.gravity, Some(25.0));
+        assert_eq!(night.ground, None);
+        let night = preset("[preset.night]\nground = \"slide\"\n").unwrap();
+        assert_eq!(night.ground, Some(Ground::Slide));
+        let err = preset("[preset.night]\nground = \"sink\"\n").unwrap_err();
+        assert!(
+            err.contains("ground needs to be through, bounce or slide"),
+            "{}",
+            err
+        );
+    }
+}
@@ -0,0 +1,235 @@
+use crate::config::parse_color;
+use crate::record::{Input, X_RANGE};
+use ratatui::style::Color;
+use std::{
+    fs::{self, File},
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+};
+
+// Commands come one per line, spelled like the inputs of a session log
+// without the tick, and launches can name their color. `x` is a share of
+// the screen width from its middle, -0.5 at the left edge and 0.5 at the
+// right one, as in a show file:
+//
+//     launch x=0.25 color=red shape=heart
+//     finale
+//     quit
+
+/// Inputs sent by other programs through a fifo, a Unix socket or stdin
+/// (`-`), read on threads of their own.
+pub struct Control {
+    inputs: Receiver<Input>,
+    // the socket we made, removed again on the way out
+    socket: Option<PathBuf>,
+}
+
+impl Control {
+    /// Reads commands from `path`: stdin for `-`, a fifo or a file that
+    /// exists, or on Unix a socket made there that any number of programs
+    /// can connect to. `palette` is what `color=` names are looked up in.
+    pub fn open(path: &Path, palette: Vec<Color>) -> io::Result<Control> {
+        let (tx, inputs) = mpsc::channel();
+        let mut socket = None;
+        if path.to_str() == Some("-") {
+            thread::spawn(move || read_commands(io::stdin().lock(), &palette, &tx, io::sink()));
+        } else if path.exists() && !is_socket(path) {
+            let path = path.to_path_buf();
+            // opening a fifo waits for a writer, which the thread can afford
+            thread::spawn(move || loop {
+                let input = match File::open(&path) {
+                    Ok(file) => BufReader::new(file),
+                    Err(_) => return,
+                };
+                // a fifo is opened again for whoever writes to it next
+                if !read_commands(input, &palette, &tx, io::sink()) || !is_fifo(&path) {
+                    return;
+                }
+            });
+        } else {
+            listen(path, palette, tx)?;
+            socket = Some(path.to_path_buf());
+        }
+        Ok(Control { inputs, socket })
+    }
+
+    /// What came in since the last call.
+    pub fn inputs(&self) -> Vec<Input> {
+        self.inputs.try_iter().collect()
+    }
+}
+
+impl Drop for Control {
+    fn drop(&mut self) {
+        if let Some(path) = &self.socket {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn is_fifo(path: &Path) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    fs::metadata(path).is_ok_and(|m| m.file_type().is_fifo())
+}
+
+#[cfg(not(unix))]
+fn is_fifo(_: &Path) -> bool {
+    false
+}
+
+#[cfg(unix)]
+fn is_socket(path: &Path) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    fs::metadata(path).is_ok_and(|m| m.file_type().is_socket())
+}
+
+#[cfg(not(unix))]
+fn is_socket(_: &Path) -> bool {
+    false
+}
+
+#[cfg(unix)]
+fn listen(path: &Path, palette: Vec<Color>, tx: Sender<Input>) -> io::Result<()> {
+    use std::os::unix::net::{UnixListener, UnixStream};
+    // a socket nobody answers on was left behind by an earlier run
+    if is_socket(path) {
+        if UnixStream::connect(path).is_ok() {
+            let err = format!("{} is already in use", path.display());
+            return Err(io::Error::new(io::ErrorKind::AddrInUse, err));
+        }
+        fs::remove_file(path)?;
+    }
+    let listener = UnixListener::bind(path)?;
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let (palette, tx) = (palette.clone(), tx.clone());
+            thread::spawn(move || {
+                if let Ok(reply) = stream.try_clone() {
+                    read_commands(BufReader::new(stream), &palette, &tx, reply);
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn listen(path: &Path, _: Vec<Color>, _: Sender<Input>) -> io::Result<()> {
+    let err = format!("{} doesn't exist", path.display());
+    Err(io::Error::new(io::ErrorKind::NotFound, err))
+}
+
+// passes on commands until the input ends, answering the ones it can't
+// make sense of; false once nobody takes them any more
+fn read_commands(
+    input: impl BufRead,
+    palette: &[Color],
+    tx: &Sender<Input>,
+    mut reply: impl Write,
+) -> bool {
+    for line in input.lines() {
+        let Ok(line) = line else { break };
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match parse_command(line, palette) {
+            Ok(input) => {
+                if tx.send(input).is_err() {
+                    return false;
+                }
+            }
+            Err(err) => {
+                let _ = writeln!(reply, "firewors: {}", err);
+            }
+        }
+    }
+    true
+}
+
+fn parse_command(line: &str, palette: &[Color]) -> Result<Input, String> {
+    // cells are a likely mistake, which would otherwise look like nonsense
+    let off_screen = line
+        .split_whitespace()
+        .filter_map(|word| word.strip_prefix("x="))
+        .find(|x| x.parse().is_ok_and(|x: f64| !X_RANGE.contains(&x)));
+    if let Some(x) = off_screen {
+        return Err(format!(
+            "x={} is off the screen, which goes from x=-0.5 at the left edge to x=0.5 at the right one",
+            x
+        ));
+    }
+    let words: Vec<String> = line
+        .split_whitespace()
+        .map(|word| match word.strip_prefix("color=") {
+            // the log only knows colors by their number key
+            Some(name) if name.parse::<usize>().is_err() => {
+                let color = parse_color(name);
+                match palette.iter().position(|c| Some(*c) == color) {
+                    Some(i) => format!("color={}", i + 1),
+                    None => word.to_string(),
+                }
+            }
+            _ => word.to_string(),
+        })
+        .collect();
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    match Input::parse(&words) {
+        // the screen goes by the terminal, only a replay can say otherwise
+        Some(Input::Resize { .. }) => {
+            Err("the screen follows the terminal, it can't be resized".to_string())
+        }
+        Some(input) => Ok(input),
+        None => Err(format!("can't make sense of `{}`", line)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keymap::Action;
+    use firewors::sim::BurstType;
+
+    #[test]
+    fn commands_read_like_the_log() {
+        let palette = [Color::Blue, Color::Red];
+        assert_eq!(
+            parse_command("finale", &palette),
+            Ok(Input::Action(Action::Finale))
+        );
+        let Ok(Input::Launch(launch)) =
+            parse_command("launch x=0.25 color=red shape=heart", &palette)
+        else {
+            panic!("not a launch");
+        };
+        assert_eq!(launch.x, Some(0.25));
+        assert_eq!(launch.color, Some(1));
+        assert_eq!(launch.burst, Some(BurstType::Heart));
+        assert_eq!(launch.speed, None);
+        assert!(parse_command("launch color=green", &palette).is_err());
+        assert!(parse_command("fireworks please", &palette).is_err());
+    }
+
+    #[test]
+    fn launches_land_on_the_screen() {
+        let Ok(Input::Launch(launch)) = parse_command("launch x=-0.5", &[]) else {
+            panic!("not a launch");
+        };
+        assert_eq!(launch.x, Some(-0.5));
+        // a column, not a share of the screen
+        let err = parse_command("launch x=10", &[]).unwrap_err();
+        assert!(err.starts_with("x=10 is off the screen"), "{}", err);
+    }
+
+    #[test]
+    fn resizes_are_refused() {
+        assert!(parse_command("resize 80x24", &[]).is_err());
+        assert!(parse_command("resize 0x0", &[]).is_err());
+        // nor would a log get away with it
+        assert_eq!(Input::parse(&["resize", "0x0"]), None);
+        assert_eq!(Input::parse(&["resize", "80x0"]), None);
+    }
+}
@@ -0,0 +1,330 @@
+use crate::keymap::Action;
+use crate::record::Input;
+use ratatui::{buffer::Buffer, layout::Rect, style::Style, widgets::Widget};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// seconds before the target that set off a rocket each, getting the crowd
+// ready for the finale
+const LAST_SECONDS: u64 = 10;
+
+/// Counts down to a moment on the wall clock and goes off when it comes.
+pub struct Countdown {
+    target: SystemTime,
+    // the whole seconds left when `due` last had something to launch
+    last_second: Option<u64>,
+    over: bool,
+}
+
+impl Countdown {
+    /// Counts down to `text`: `HH:MM[:SS]`, the next time the clock shows
+    /// it, or `YYYY-MM-DD HH:MM[:SS]`, both in local time.
+    pub fn parse(text: &str) -> Result<Countdown, String> {
+        let bad = || format!("`{}` is not a time like 23:59 or 2025-12-31 23:59", text);
+        let (date, time) = match text.trim().split_once([' ', 'T']) {
+            Some((date, time)) => (Some(date), time),
+            None => (None, text.trim()),
+        };
+        let clock = parse_clock(time).ok_or_else(bad)?;
+        let target = match date {
+            Some(date) => match numbers(date, '-').ok_or_else(bad)?[..] {
+                [y, mo, d] if (1..=12).contains(&mo) && (1..=31).contains(&d) => {
+                    local_time([y, mo, d], clock).ok_or_else(bad)?
+                }
+                _ => return Err(bad()),
+            },
+            None => next_at(clock, SystemTime::now()).ok_or_else(bad)?,
+        };
+        Ok(Countdown::to(target))
+    }
+
+    /// Counts down to the coming midnight.
+    pub fn midnight() -> Countdown {
+        let [y, mo, d] = local_date(SystemTime::now());
+        let target = local_time([y, mo, d + 1], [0, 0, 0]).unwrap_or_else(SystemTime::now);
+        Countdown::to(target)
+    }
+
+    fn to(target: SystemTime) -> Countdown {
+        Countdown {
+            target,
+            last_second: None,
+            over: false,
+        }
+    }
+
+    /// Time left at `now`, or None once it's gone off.
+    pub fn remaining(&self, now: SystemTime) -> Option<Duration> {
+        match self.over {
+            true => None,
+            false => Some(self.target.duration_since(now).unwrap_or(Duration::ZERO)),
+        }
+    }
+
+    /// A rocket for each of the last seconds and the finale at zero.
+    pub fn due(&mut self, now: SystemTime) -> Vec<Input> {
+        let left = match self.remaining(now) {
+            Some(left) => left,
+            None => return Vec::new(),
+        };
+        if left.is_zero() {
+            self.over = true;
+            return vec![
+                Input::Action(Action::MultiLaunch),
+                Input::Action(Action::Finale),
+            ];
+        }
+        let second = left.as_secs();
+        if second >= LAST_SECONDS || self.last_second == Some(second) {
+            return Vec::new();
+        }
+        self.last_second = Some(second);
+        vec![Input::Action(Action::Launch)]
+    }
+}
+
+fn numbers(s: &str, sep: char) -> Option<Vec<i64>> {
+    s.split(sep).map(|n| n.parse().ok()).collect()
+}
+
+/// `HH:MM[:SS]` as the hour, minute and second.
+pub fn parse_clock(text: &str) -> Option<[i64; 3]> {
+    let (hour, minute, second) = match numbers(text, ':')?[..] {
+        [h, m] => (h, m, 0),
+        [h, m, s] => (h, m, s),
+        _ => return None,
+    };
+    if !(0..24).contains(&hour) || !(0..60).contains(&minute) || !(0..60).contains(&second) {
+        return None;
+    }
+    Some([hour, minute, second])
+}
+
+/// The first moment after `now` the local clock reads `clock`, today or
+/// tomorrow.
+pub fn next_at(clock: [i64; 3], now: SystemTime) -> Option<SystemTime> {
+    let today = local_date(now);
+    let at = local_time(today, clock)?;
+    match at > now {
+        true => Some(at),
+        // already gone by today; mktime rolls the 32nd over
+        false => local_time([today[0], today[1], today[2] + 1], clock),
+    }
+}
+
+/// The local midnight that started the day `now` is in.
+pub fn midnight_before(now: SystemTime) -> Option<SystemTime> {
+    local_time(local_date(now), [0, 0, 0])
+}
+
+/// How `left` reads on the clock: `MM:SS` under an hour, `HH:MM:SS` under a
+/// day and days on top of that.
+pub fn clock(left: Duration) -> String {
+    // round up, so it shows 00:00 only when it's over
+    let secs = left.as_secs() + u64::from(left.subsec_nanos() > 0);
+    let (days, hours, minutes, seconds) =
+        (secs / 86400, secs / 3600 % 24, secs / 60 % 60, secs % 60);
+    match (days, hours) {
+        (0, 0) => format!("{:02}:{:02}", minutes, seconds),
+        (0, _) => format!("{:02}:{:02}:{:02}", hours, minutes, seconds),
+        _ => format!("{}d {:02}:{:02}:{:02}", days, hours, minutes, seconds),
+    }
+}
+
+// digits three blocks wide and five tall, each row a bitmask with the
+// leftmost block in bit 2
+fn digit(c: char) -> Option<[u8; 5]> {
+    let rows = match c {
+        '0' => [7, 5, 5, 5, 7],
+        '1' => [2, 6, 2, 2, 7],
+        '2' => [7, 1, 7, 4, 7],
+        '3' => [7, 1, 7, 1, 7],
+        '4' => [5, 5, 7, 1, 1],
+        '5' => [7, 4, 7, 1, 7],
+        '6' => [7, 4, 7, 5, 7],
+        '7' => [7, 1, 2, 2, 2],
+        '8' => [7, 5, 7, 5, 7],
+        '9' => [7, 5, 7, 1, 7],
+        ':' => [0, 2, 0, 2, 0],
+        _ => return None,
+    };
+    Some(rows)
+}
+
+/// The clock in big block digits, centered at the top of the area; falls
+/// back to plain text when that doesn't fit.
+pub struct BigClock<'a> {
+    pub text: &'a str,
+    pub style: Style,
+}
+
+impl BigClock<'_> {
+    // each block is two cells wide so the digits aren't squashed
+    const BLOCK: u16 = 2;
+    const HEIGHT: u16 = 5;
+}
+
+impl Widget for BigClock<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        // days go above the clock in plain text
+        let (days, time) = match self.text.split_once(' ') {
+            Some((days, time)) => (Some(days), time),
+            None => (None, self.text),
+        };
+        let centered = |text: &str, y: u16, buf: &mut Buffer| {
+            let width = text.chars().count() as u16;
+            let x = area.x + area.width.saturating_sub(width) / 2;
+            buf.set_stringn(x, y, text, usize::from(area.width), self.style);
+        };
+        let width = (time.chars().count() as u16 * 4).saturating_sub(1) * Self::BLOCK;
+        let big = time.chars().all(|c| digit(c).is_some());
+        if !big || width > area.width || Self::HEIGHT + 2 > area.height {
+            centered(self.text, area.y, buf);
+            return;
+        }
+        if let Some(days) = days {
+            centered(days, area.y, buf);
+        }
+        let left = area.x + (area.width - width) / 2;
+        for (i, c) in time.chars().enumerate() {
+            let rows = digit(c).unwrap_or_default();
+            for (row, bits) in rows.iter().enumerate() {
+                for column in 0..3u16 {
+                    if bits & (4 >> column) == 0 {
+                        continue;
+                    }
+                    let x = left + (i as u16 * 4 + column) * Self::BLOCK;
+                    let y = area.y + 1 + row as u16;
+                    for dx in 0..Self::BLOCK {
+                        buf[(x + dx, y)].set_symbol("█").set_style(self.style);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// `time` on the local clock as `YYYYMMDD-HHMMSS`, for naming files.
+pub fn timestamp(time: SystemTime) -> String {
+    let [y, mo, d, h, mi, s] = local(time);
+    format!("{:04}{:02}{:02}-{:02}{:02}{:02}", y, mo, d, h, mi, s)
+}
+
+// the local calendar date at `time`
+fn local_date(time: SystemTime) -> [i64; 3] {
+    let [y, mo, d, ..] = local(time);
+    [y, mo, d]
+}
+
+// the local date and time of day at `time`
+#[cfg(unix)]
+fn local(time: SystemTime) -> [i64; 6] {
+    let secs = time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let time = secs as libc::time_t;
+    let mut tm = zeroed_tm();
+    // SAFETY: both pointers are to live locals; localtime_r is the
+    // thread-safe one
+    unsafe { libc::localtime_r(&time, &mut tm) };
+    [
+        i64::from(tm.tm_year) + 1900,
+        i64::from(tm.tm_mon) + 1,
+        i64::from(tm.tm_mday),
+        i64::from(tm.tm_hour),
+        i64::from(tm.tm_min),
+        i64::from(tm.tm_sec),
+    ]
+}
+
+// the moment the local clock reads `hms` on `ymd`; days past the end of the
+// month roll over into the next
+#[cfg(unix)]
+fn local_time(ymd: [i64; 3], hms: [i64; 3]) -> Option<SystemTime> {
+    let mut tm = zeroed_tm();
+    tm.tm_year = i32::try_from(ymd[0] - 1900).ok()?;
+    tm.tm_mon = (ymd[1] - 1) as i32;
+    tm.tm_mday = ymd[2] as i32;
+    tm.tm_hour = hms[0] as i32;
+    tm.tm_min = hms[1] as i32;
+    tm.tm_sec = hms[2] as i32;
+    // let the time zone rules work out daylight saving
+    tm.tm_isdst = -1;
+    // SAFETY: mktime only reads and normalizes the tm it's given
+    let secs = unsafe { libc::mktime(&mut tm) };
+    let secs = u64::try_from(secs).ok()?;
+    Some(UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+#[cfg(unix)]
+fn zeroed_tm() -> libc::tm {
+    // SAFETY: tm is plain integers, and on some systems a zone name pointer
+    // for which null is fine
+    unsafe { std::mem::zeroed() }
+}
+
+// elsewhere the clock is taken to be on UTC
+#[cfg(not(unix))]
+fn local(time: SystemTime) -> [i64; 6] {
+    let secs = time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    // days since 0000-03-01, in 400 year eras, after Howard Hinnant's
+    // civil_from_days
+    let z = (secs / 86400) as i64 + 719468;
+    let (era, doe) = (z.div_euclid(146097), z.rem_euclid(146097));
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    let clock = (secs % 86400) as i64;
+    [year, month, day, clock / 3600, clock / 60 % 60, clock % 60]
+}
+
+#[cfg(not(unix))]
+fn local_time(ymd: [i64; 3], hms: [i64; 3]) -> Option<SystemTime> {
+    let [year, month, day] = ymd;
+    let year = year - i64::from(month <= 2);
+    let (era, yoe) = (year.div_euclid(400), year.rem_euclid(400));
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+    let secs = days * 86400 + hms[0] * 3600 + hms[1] * 60 + hms[2];
+    Some(UNIX_EPOCH + Duration::from_secs(u64::try_from(secs).ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clock_reads_like_a_clock() {
+        assert_eq!(clock(Duration::from_millis(61_500)), "01:02");
+        assert_eq!(clock(Duration::from_secs(3600)), "01:00:00");
+        assert_eq!(clock(Duration::from_secs(2 * 86400 + 5)), "2d 00:00:05");
+        assert_eq!(clock(Duration::ZERO), "00:00");
+    }
+
+    #[test]
+    fn last_seconds_then_finale() {
+        let start = UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let mut countdown = Countdown::to(start + Duration::from_secs(12));
+        let mut launches = 0;
+        for ms in (0..12_000).step_by(10) {
+            let due = countdown.due(start + Duration::from_millis(ms));
+            assert!(due.iter().all(|i| *i == Input::Action(Action::Launch)));
+            launches += due.len();
+        }
+        assert_eq!(launches, LAST_SECONDS as usize);
+        let due = countdown.due(start + Duration::from_secs(12));
+        assert!(due.contains(&Input::Action(Action::Finale)));
+        assert!(countdown.remaining(start).is_none());
+        assert!(countdown.due(start + Duration::from_secs(13)).is_empty());
+    }
+
+    #[test]
+    fn rejects_what_isnt_a_time() {
+        for text in ["24:00", "12", "12:60", "noon", "2025-13-01 00:00"] {
+            assert!(Countdown::parse(text).is_err(), "{}", text);
+        }
+        assert!(Countdown::parse("2025-12-31 23:59:30").is_ok());
+    }
+}
@@ -0,0 +1,185 @@
+use crate::config::THEMES;
+use crate::schedule::Launch;
+use firewors::sim::BurstType;
+use rand::{rngs::StdRng, Rng};
+
+/// What a demo shows, one after the other, before starting over.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Scene {
+    Rockets,
+    Shapes,
+    Themes,
+    Finale,
+}
+
+const SCENES: [Scene; 4] = [Scene::Rockets, Scene::Shapes, Scene::Themes, Scene::Finale];
+
+// seconds between two launches in each scene, and the launches each theme
+// gets before the next one
+const ROCKET_GAP: f64 = 0.8;
+const SHAPE_GAP: f64 = 1.2;
+const THEME_GAP: f64 = 0.4;
+const THEME_LAUNCHES: usize = 6;
+// the least a finale may take, for it to burn out before the next round
+const FINALE_SECS: f64 = 16.0;
+
+impl Scene {
+    fn caption(self) -> &'static str {
+        match self {
+            Scene::Rockets => "single rockets",
+            Scene::Shapes => "shaped bursts",
+            Scene::Themes => "themes",
+            Scene::Finale => "grand finale",
+        }
+    }
+}
+
+/// What the demo wants done.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cue {
+    // shown under the fireworks until the next one
+    Caption(String),
+    // a built-in theme, or None to go back to the palette it started with
+    Theme(Option<&'static str>),
+    Launch(Launch),
+    Finale,
+}
+
+/// Goes round the scenes on its own, a few seconds each, as a showcase of
+/// what firewors does.
+pub struct Demo {
+    // ticks a scene lasts, and the length of a tick in seconds
+    scene_ticks: u64,
+    tick: f64,
+    // its own, so a recording of the demo replays without it
+    rng: StdRng,
+    scene: usize,
+    // when the scene started, None before the first one, how many launches
+    // it made and when the next one goes up
+    start: Option<u64>,
+    launches: usize,
+    next_at: u64,
+}
+
+impl Demo {
+    /// A demo giving every scene `secs` seconds, in simulation steps of
+    /// `tick` seconds, placing its shaped bursts with `rng`.
+    pub fn new(secs: f64, tick: f64, rng: StdRng) -> Demo {
+        Demo {
+            scene_ticks: (secs / tick).round().max(1.0) as u64,
+            tick,
+            rng,
+            scene: 0,
+            start: None,
+            launches: 0,
+            next_at: 0,
+        }
+    }
+
+    pub fn scene(&self) -> Scene {
+        SCENES[self.scene]
+    }
+
+    /// What to do before step `tick`.
+    pub fn due(&mut self, tick: u64) -> Vec<Cue> {
+        let mut cues = Vec::new();
+        match self.start {
+            None => self.begin(tick, &mut cues),
+            Some(start) if tick >= start + self.length() => {
+                self.scene = (self.scene + 1) % SCENES.len();
+                self.begin(tick, &mut cues);
+            }
+            Some(_) => {}
+        }
+        if tick < self.next_at {
+            return cues;
+        }
+        let anywhere = Launch {
+            x: None,
+            color: None,
+            burst: None,
+            speed: None,
+        };
+        let gap = match self.scene() {
+            Scene::Rockets => {
+                cues.push(Cue::Launch(anywhere));
+                ROCKET_GAP
+            }
+            Scene::Shapes => {
+                let shape = BurstType::SHAPES[self.launches % BurstType::SHAPES.len()];
+                cues.push(Cue::Caption(format!("shaped bursts: {}", shape.name())));
+                cues.push(Cue::Launch(Launch {
+                    x: Some(self.rng.gen_range(-0.25..0.25)),
+                    burst: Some(shape),
+                    ..anywhere
+                }));
+                SHAPE_GAP
+            }
+            Scene::Themes => {
+                if self.launches.is_multiple_of(THEME_LAUNCHES) {
+                    let (name, _) = THEMES[self.launches / THEME_LAUNCHES % THEMES.len()];
+                    cues.push(Cue::Caption(format!("themes: {}", name)));
+                    cues.push(Cue::Theme(Some(name)));
+                }
+                cues.push(Cue::Launch(anywhere));
+                THEME_GAP
+            }
+            Scene::Finale => {
+                self.next_at = u64::MAX;
+                return cues;
+            }
+        };
+        self.launches += 1;
+        self.next_at = tick + (gap / self.tick).round().max(1.0) as u64;
+        cues
+    }
+
+    fn begin(&mut self, tick: u64, cues: &mut Vec<Cue>) {
+        self.start = Some(tick);
+        self.launches = 0;
+        self.next_at = tick;
+        cues.push(Cue::Caption(self.scene().caption().to_string()));
+        match self.scene() {
+            Scene::Rockets => cues.push(Cue::Theme(None)),
+            Scene::Finale => cues.push(Cue::Finale),
+            Scene::Shapes | Scene::Themes => {}
+        }
+    }
+
+    // ticks the scene under way lasts
+    fn length(&self) -> u64 {
+        match self.scene() {
+            Scene::Finale => self.scene_ticks.max((FINALE_SECS / self.tick) as u64),
+            _ => self.scene_ticks,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn goes_round_every_scene() {
+        let mut demo = Demo::new(5.0, 0.01, StdRng::seed_from_u64(1));
+        let mut seen = Vec::new();
+        let mut finales = 0;
+        for tick in 0..3200 {
+            for cue in demo.due(tick) {
+                match cue {
+                    Cue::Finale => finales += 1,
+                    Cue::Theme(name) if tick == 3100 => assert_eq!(name, None),
+                    _ => {}
+                }
+            }
+            if seen.last() != Some(&demo.scene()) {
+                seen.push(demo.scene());
+            }
+        }
+        // the finale gets the time it needs to burn out, not just five
+        // seconds, before the rockets come back in the first palette
+        assert_eq!(seen, [SCENES.as_slice(), &[Scene::Rockets]].concat());
+        assert_eq!(finales, 1);
+    }
+}
@@ -0,0 +1,52 @@
+use crate::{MIN_HEIGHT, MIN_WIDTH};
+use std::{convert::Infallible, fmt, io};
+
+/// Whatever stops firewors from starting or going on, worded for the user.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// The config file, or a preset or theme picked from it, doesn't make
+    /// sense.
+    #[error("{0}")]
+    Config(String),
+    /// A show file doesn't make sense.
+    #[error("{0}")]
+    Show(String),
+    /// `--countdown` isn't a time.
+    #[error("{0}")]
+    Countdown(String),
+    /// `export` can't save in the format asked for.
+    #[error("{0}")]
+    Export(String),
+    /// Something given on the command line couldn't be opened: a file, a
+    /// socket, an address or a player.
+    #[error("{what}: {source}")]
+    Open { what: String, source: io::Error },
+    /// The terminal is smaller than the least we can draw in.
+    #[error(
+        "the terminal is {width}x{height}, it needs to be at least {}x{}",
+        MIN_WIDTH,
+        MIN_HEIGHT
+    )]
+    TooSmall { width: u16, height: u16 },
+    /// The simulation thread panicked.
+    #[error("the simulation crashed")]
+    Crashed,
+    /// Reading or writing went wrong anywhere else, the terminal included.
+    #[error("{0}")]
+    Io(#[from] io::Error),
+}
+
+impl Error {
+    /// For `map_err`, naming what couldn't be opened.
+    pub fn open(what: impl fmt::Display) -> impl FnOnce(io::Error) -> Error {
+        let what = what.to_string();
+        move |source| Error::Open { what, source }
+    }
+}
+
+// for the drawing off screen, which can't fail
+impl From<Infallible> for Error {
+    fn from(never: Infallible) -> Error {
+        match never {}
+    }
+}
@@ -0,0 +1,129 @@
+use crate::ansi::Depth;
+use crate::args::{Args, Export};
+use crate::error::Error;
+use crate::gif::Gif;
+use crate::png::Apng;
+use crate::{ansi, auto_show, drawn_particles, open_session, tick_secs, App, Session};
+use ratatui::{layout::Rect, style::Color};
+use std::{io, path::Path};
+
+// pixels a braille dot takes each way
+pub const DOT: usize = 2;
+
+// what the output's extension says to save it as
+enum Animation {
+    Gif(Gif),
+    Png(Apng),
+}
+
+impl Animation {
+    fn create(path: &Path, width: usize, height: usize, delay: u16) -> Result<Animation, Error> {
+        let extension = path.extension().and_then(|e| e.to_str());
+        let animation = match extension.map(str::to_ascii_lowercase).as_deref() {
+            Some("gif") => {
+                Gif::create(path, width as u16, height as u16, delay).map(Animation::Gif)
+            }
+            Some("png" | "apng") => {
+                Apng::create(path, width as u32, height as u32, delay).map(Animation::Png)
+            }
+            _ => {
+                return Err(Error::Export(format!(
+                    "can't tell what to save {} as, it needs to end in .gif, .png or .apng",
+                    path.display()
+                )))
+            }
+        };
+        animation.map_err(Error::open(path.display()))
+    }
+
+    // red, green and blue row by row
+    fn frame(&mut self, pixels: &[u8]) -> io::Result<()> {
+        match self {
+            Animation::Gif(gif) => {
+                let indices: Vec<u8> = pixels
+                    .chunks(3)
+                    .map(|rgb| Gif::index(rgb[0], rgb[1], rgb[2]))
+                    .collect();
+                gif.frame(&indices)
+            }
+            Animation::Png(png) => png.frame(pixels),
+        }
+    }
+
+    fn finish(self) -> io::Result<()> {
+        match self {
+            Animation::Gif(gif) => gif.finish(),
+            Animation::Png(png) => png.finish(),
+        }
+    }
+}
+
+/// Plays the session headlessly, saving a frame every so often: the auto
+/// show, unless a show file, a replay or a countdown has the launches.
+pub fn run(mut app: App, args: &Args, export: &Export) -> Result<(), Error> {
+    let (width, height) = (export.width, export.height);
+    open_session(&mut app, args, (width, height))?;
+    if app.show.is_none() && app.replay.is_none() && app.countdown.is_none() {
+        app.auto = app
+            .auto
+            .take()
+            .or_else(|| Some(auto_show(args, &app.pacing)));
+    }
+    app.depth = Depth::Truecolor;
+
+    let tick = tick_secs(args);
+    let frame_secs = 1.0 / f64::from(export.fps);
+    // both count their delays in hundredths of a second here
+    let delay = (frame_secs * 100.0).round().max(1.0) as u16;
+    let (image_width, image_height) = image_size(width, height);
+    let mut animation = Animation::create(&export.output, image_width, image_height, delay)?;
+
+    let mut session = Session::new(app, Rect::new(0, 0, width, height))?;
+    let frames = (export.duration / frame_secs).round() as u64;
+    let mut shown = 0.0;
+    let mut pixels = vec![0; image_width * image_height * 3];
+    'frames: for frame in 0..frames {
+        pixels.fill(0);
+        rasterize(&session.app, width, height, |dx, dy, color| {
+            let (r, g, b) = ansi::to_rgb(color);
+            for row in dy..dy + DOT {
+                for column in dx..dx + DOT {
+                    let i = (row * image_width + column) * 3;
+                    pixels[i..i + 3].copy_from_slice(&[r, g, b]);
+                }
+            }
+        });
+        animation.frame(&pixels)?;
+
+        // as many steps as it takes to keep up with the frames
+        while shown < (frame + 1) as f64 * frame_secs {
+            if !session.step(tick)? {
+                break 'frames;
+            }
+            shown += tick;
+        }
+    }
+    Ok(animation.finish()?)
+}
+
+/// Pixels across and down a picture of a `width` by `height` screen.
+pub fn image_size(width: u16, height: u16) -> (usize, usize) {
+    (usize::from(width) * 2 * DOT, usize::from(height) * 4 * DOT)
+}
+
+/// Goes over the dots the braille canvas would light on a `width` by
+/// `height` screen, by the top left of their DOT by DOT pixels.
+pub fn rasterize(app: &App, width: u16, height: u16, mut plot: impl FnMut(usize, usize, Color)) {
+    let (image_width, image_height) = image_size(width, height);
+    for (x, y, color, _) in drawn_particles(app) {
+        let dx = (x + f64::from(width) / 2.0) * 2.0;
+        let dy = (f64::from(height) / 2.0 - y) * 4.0;
+        if dx < 0.0 || dy < 0.0 {
+            continue;
+        }
+        let (dx, dy) = (dx as usize * DOT, dy as usize * DOT);
+        if dx < image_width && dy < image_height {
+            plot(dx, dy, color);
+        }
+    }
+}
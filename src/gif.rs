@@ -0,0 +1,148 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+// the palette every frame shares: a 6x6x6 color cube
+const LEVELS: [u8; 6] = [0, 51, 102, 153, 204, 255];
+
+/// An animated GIF, looping forever, written a frame at a time.
+pub struct Gif {
+    out: BufWriter<File>,
+    width: u16,
+    height: u16,
+    // hundredths of a second each frame stays up
+    delay: u16,
+}
+
+impl Gif {
+    pub fn create(path: &Path, width: u16, height: u16, delay: u16) -> io::Result<Gif> {
+        let mut out = BufWriter::new(File::create(path)?);
+        out.write_all(b"GIF89a")?;
+        out.write_all(&width.to_le_bytes())?;
+        out.write_all(&height.to_le_bytes())?;
+        // a global table of 256 colors, 8 bits each, background 0
+        out.write_all(&[0xf7, 0, 0])?;
+        for i in 0..256 {
+            let rgb = match i < 216 {
+                true => [LEVELS[i / 36], LEVELS[i / 6 % 6], LEVELS[i % 6]],
+                false => [0, 0, 0],
+            };
+            out.write_all(&rgb)?;
+        }
+        // the NETSCAPE2.0 extension, repeating forever
+        out.write_all(&[0x21, 0xff, 0x0b])?;
+        out.write_all(b"NETSCAPE2.0")?;
+        out.write_all(&[0x03, 0x01, 0x00, 0x00, 0x00])?;
+        Ok(Gif {
+            out,
+            width,
+            height,
+            delay,
+        })
+    }
+
+    /// The palette entry closest to a color.
+    pub fn index(r: u8, g: u8, b: u8) -> u8 {
+        let level = |c: u8| (usize::from(c) + 25) / 51;
+        (level(r) * 36 + level(g) * 6 + level(b)) as u8
+    }
+
+    /// Adds a frame of palette indices, row by row.
+    pub fn frame(&mut self, pixels: &[u8]) -> io::Result<()> {
+        // graphic control: the delay, no transparency
+        self.out.write_all(&[0x21, 0xf9, 0x04, 0x00])?;
+        self.out.write_all(&self.delay.to_le_bytes())?;
+        self.out.write_all(&[0x00, 0x00])?;
+        // the image covers the whole screen and uses the global table
+        self.out.write_all(&[0x2c, 0, 0, 0, 0])?;
+        self.out.write_all(&self.width.to_le_bytes())?;
+        self.out.write_all(&self.height.to_le_bytes())?;
+        self.out.write_all(&[0x00, 0x08])?;
+        for block in lzw(pixels).chunks(255) {
+            self.out.write_all(&[block.len() as u8])?;
+            self.out.write_all(block)?;
+        }
+        self.out.write_all(&[0x00])
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        self.out.write_all(&[0x3b])?;
+        self.out.flush()
+    }
+}
+
+const CLEAR: u16 = 256;
+const END: u16 = 257;
+const MAX_CODE: u16 = 4095;
+
+// variable length LZW over 8-bit pixels, as GIF has it: codes start out 9
+// bits long, grow up to 12 and the table starts over once it's full
+fn lzw(pixels: &[u8]) -> Vec<u8> {
+    let mut bits = Bits::default();
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next = END + 1;
+    let mut size = 9;
+    bits.push(CLEAR, size);
+    let mut pixels = pixels.iter();
+    let mut prefix = match pixels.next() {
+        Some(p) => u16::from(*p),
+        None => {
+            bits.push(END, size);
+            return bits.finish();
+        }
+    };
+    for &pixel in pixels {
+        if let Some(&code) = table.get(&(prefix, pixel)) {
+            prefix = code;
+            continue;
+        }
+        bits.push(prefix, size);
+        if next > MAX_CODE {
+            bits.push(CLEAR, size);
+            table.clear();
+            next = END + 1;
+            size = 9;
+        } else {
+            table.insert((prefix, pixel), next);
+            // the decoder grows its codes as soon as it adds this one
+            if next == 1 << size && size < 12 {
+                size += 1;
+            }
+            next += 1;
+        }
+        prefix = u16::from(pixel);
+    }
+    bits.push(prefix, size);
+    bits.push(END, size);
+    bits.finish()
+}
+
+// packs codes least significant bit first
+#[derive(Default)]
+struct Bits {
+    bytes: Vec<u8>,
+    acc: u32,
+    len: u32,
+}
+
+impl Bits {
+    fn push(&mut self, code: u16, size: u32) {
+        self.acc |= u32::from(code) << self.len;
+        self.len += size;
+        while self.len >= 8 {
+            self.bytes.push(self.acc as u8);
+            self.acc >>= 8;
+            self.len -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.len > 0 {
+            self.bytes.push(self.acc as u8);
+        }
+        self.bytes
+    }
+}
@@ -0,0 +1,331 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use firewors::sim::BurstType;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Action {
+    Quit,
+    Launch,
+    MultiLaunch,
+    LaunchColor(usize),
+    // a rocket bursting into that shape, unbound unless the config says
+    LaunchShape(BurstType),
+    Pause,
+    CycleShape,
+    CycleDepth,
+    CycleGround,
+    ToggleHud,
+    ToggleBraille,
+    CycleFountains,
+    ToggleTrails,
+    ToggleSmoke,
+    SlowDown,
+    SpeedUp,
+    Finale,
+    LaunchMessage,
+    WindLeft,
+    WindRight,
+    MoreTurbulence,
+    LessTurbulence,
+    MoreGravity,
+    LessGravity,
+    MoreDrag,
+    LessDrag,
+    MoreSparks,
+    FewerSparks,
+    ToggleAim,
+    TiltLeft,
+    TiltRight,
+    Fire,
+    #[cfg(feature = "sound")]
+    ToggleSound,
+    Screenshot,
+}
+
+impl Action {
+    // the actions that can be rebound, by their name in the config file
+    const NAMED: &'static [(&'static str, Action)] = &[
+        ("quit", Action::Quit),
+        ("launch", Action::Launch),
+        ("multi_launch", Action::MultiLaunch),
+        ("pause", Action::Pause),
+        ("cycle_shape", Action::CycleShape),
+        ("cycle_depth", Action::CycleDepth),
+        ("cycle_ground", Action::CycleGround),
+        ("toggle_hud", Action::ToggleHud),
+        ("toggle_braille", Action::ToggleBraille),
+        ("cycle_fountains", Action::CycleFountains),
+        ("toggle_trails", Action::ToggleTrails),
+        ("toggle_smoke", Action::ToggleSmoke),
+        ("slow_down", Action::SlowDown),
+        ("speed_up", Action::SpeedUp),
+        ("finale", Action::Finale),
+        ("launch_message", Action::LaunchMessage),
+        ("wind_left", Action::WindLeft),
+        ("wind_right", Action::WindRight),
+        ("more_turbulence", Action::MoreTurbulence),
+        ("less_turbulence", Action::LessTurbulence),
+        ("more_gravity", Action::MoreGravity),
+        ("less_gravity", Action::LessGravity),
+        ("more_drag", Action::MoreDrag),
+        ("less_drag", Action::LessDrag),
+        ("more_sparks", Action::MoreSparks),
+        ("fewer_sparks", Action::FewerSparks),
+        ("toggle_aim", Action::ToggleAim),
+        ("tilt_left", Action::TiltLeft),
+        ("tilt_right", Action::TiltRight),
+        ("fire", Action::Fire),
+        #[cfg(feature = "sound")]
+        ("toggle_sound", Action::ToggleSound),
+        ("screenshot", Action::Screenshot),
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::LaunchColor(_) => "launch_color",
+            Action::LaunchShape(_) => "launch_shape",
+            action => Action::NAMED
+                .iter()
+                .find(|(_, a)| *a == action)
+                .map_or("", |(name, _)| name),
+        }
+    }
+
+    /// The action called `name` in the config file, `launch_<shape>` for
+    /// a rocket of a given shape and `launch_color_<n>` for one in the
+    /// palette's `n`th color, counting from 1 like the digit keys.
+    pub fn from_name(name: &str) -> Option<Action> {
+        // what it went by when it only turned bouncing on and off
+        if name == "toggle_bounce" {
+            return Some(Action::CycleGround);
+        }
+        let named = Action::NAMED.iter().find(|(n, _)| *n == name);
+        match named {
+            Some((_, action)) => Some(*action),
+            None => {
+                let rest = name.strip_prefix("launch_")?;
+                if let Some(n) = rest.strip_prefix("color_") {
+                    let n: usize = n.parse().ok()?;
+                    return n.checked_sub(1).map(Action::LaunchColor);
+                }
+                BurstType::from_name(rest).map(Action::LaunchShape)
+            }
+        }
+    }
+}
+
+/// A key and the modifiers held down with it. Shift is taken as part of
+/// the character it types, so `+` is `+` whichever key it takes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Key {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl Key {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Key {
+        match code {
+            KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) => Key {
+                code: KeyCode::Char(c.to_ascii_uppercase()),
+                modifiers: modifiers - KeyModifiers::SHIFT,
+            },
+            _ => Key { code, modifiers },
+        }
+    }
+
+    fn ctrl(c: char) -> Key {
+        Key::new(KeyCode::Char(c), KeyModifiers::CONTROL)
+    }
+}
+
+impl From<KeyCode> for Key {
+    fn from(code: KeyCode) -> Key {
+        Key::new(code, KeyModifiers::NONE)
+    }
+}
+
+impl From<KeyEvent> for Key {
+    fn from(event: KeyEvent) -> Key {
+        Key::new(event.code, event.modifiers)
+    }
+}
+
+pub struct Keymap {
+    bindings: Vec<(Action, Key)>,
+}
+
+impl Keymap {
+    pub fn new(colors: usize) -> Keymap {
+        let mut bindings = vec![
+            (Action::Quit, KeyCode::Char('q').into()),
+            // raw mode keeps ^C from reaching us as a signal
+            (Action::Quit, Key::ctrl('c')),
+            (Action::Launch, KeyCode::Char('f').into()),
+            (Action::MultiLaunch, KeyCode::Char('m').into()),
+            (Action::Pause, KeyCode::Char(' ').into()),
+            (Action::Pause, KeyCode::Char('p').into()),
+            (Action::CycleShape, KeyCode::Char('e').into()),
+            (Action::CycleDepth, KeyCode::Char('c').into()),
+            (Action::CycleGround, KeyCode::Char('o').into()),
+            (Action::ToggleHud, KeyCode::Char('h').into()),
+            (Action::ToggleHud, KeyCode::Char('i').into()),
+            (Action::ToggleBraille, KeyCode::Char('b').into()),
+            (Action::CycleFountains, KeyCode::Char('s').into()),
+            (Action::ToggleTrails, KeyCode::Char('t').into()),
+            (Action::ToggleSmoke, KeyCode::Char('k').into()),
+            (Action::SlowDown, KeyCode::Char(',').into()),
+            (Action::SpeedUp, KeyCode::Char('.').into()),
+            (Action::Finale, KeyCode::Char('g').into()),
+            (Action::LaunchMessage, KeyCode::Char('w').into()),
+            (Action::WindLeft, KeyCode::Left.into()),
+            (Action::WindRight, KeyCode::Right.into()),
+            (Action::MoreTurbulence, KeyCode::Up.into()),
+            (Action::LessTurbulence, KeyCode::Down.into()),
+            (Action::MoreGravity, KeyCode::Char(']').into()),
+            (Action::LessGravity, KeyCode::Char('[').into()),
+            (Action::MoreDrag, KeyCode::Char('\'').into()),
+            (Action::LessDrag, KeyCode::Char(';').into()),
+            (Action::MoreSparks, KeyCode::Char('=').into()),
+            (Action::MoreSparks, KeyCode::Char('+').into()),
+            (Action::FewerSparks, KeyCode::Char('-').into()),
+            (Action::ToggleAim, KeyCode::Char('a').into()),
+            (Action::TiltLeft, KeyCode::Char('z').into()),
+            (Action::TiltRight, KeyCode::Char('x').into()),
+            (Action::Fire, KeyCode::Enter.into()),
+            #[cfg(feature = "sound")]
+            (Action::ToggleSound, KeyCode::Char('n').into()),
+            (Action::Screenshot, KeyCode::Char('S').into()),
+        ];
+        for (i, c) in ('1'..='9').take(colors).enumerate() {
+            bindings.push((Action::LaunchColor(i), KeyCode::Char(c).into()));
+        }
+        Keymap { bindings }
+    }
+
+    /// Moves `action` to `keys`, replacing its default bindings and
+    /// whatever those keys did before.
+    pub fn bind(&mut self, action: Action, keys: &[Key]) {
+        self.bindings
+            .retain(|(a, k)| *a != action && !keys.contains(k));
+        self.bindings.extend(keys.iter().map(|key| (action, *key)));
+    }
+
+    /// The first key bound to `action`, if any is.
+    pub fn key(&self, action: Action) -> Option<Key> {
+        self.bindings
+            .iter()
+            .find(|(a, _)| *a == action)
+            .map(|(_, key)| *key)
+    }
+
+    pub fn resolve(&self, key: Key) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(_, k)| *k == key)
+            .map(|(action, _)| *action)
+    }
+}
+
+/// How `parse_key` would spell `key`.
+pub fn key_name(key: Key) -> String {
+    let mut name = String::new();
+    if let (KeyCode::Char(c), KeyModifiers::CONTROL) = (key.code, key.modifiers) {
+        return format!("^{}", c);
+    }
+    for (modifier, prefix) in MODIFIERS {
+        if key.modifiers.contains(modifier) {
+            name.push_str(prefix);
+        }
+    }
+    match (KEYS.iter().find(|(_, k)| *k == key.code), key.code) {
+        (Some((named, _)), _) => name.push_str(named),
+        (None, KeyCode::Char(c)) => name.push(c),
+        (None, KeyCode::F(n)) => name.push_str(&format!("f{}", n)),
+        (None, _) => name.push('?'),
+    }
+    name
+}
+
+/// The key spelled `name`: a character, one of `KEYS` or `f1` to `f12`,
+/// after any of `ctrl+`, `alt+` and `shift+`, with `^x` short for `ctrl+x`.
+pub fn parse_key(name: &str) -> Option<Key> {
+    let mut rest = name;
+    let mut modifiers = KeyModifiers::NONE;
+    loop {
+        let prefix = MODIFIERS.iter().find(|(_, prefix)| {
+            rest.len() > prefix.len()
+                && rest
+                    .get(..prefix.len())
+                    .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+        });
+        match prefix {
+            Some((modifier, prefix)) => {
+                modifiers |= *modifier;
+                rest = &rest[prefix.len()..];
+            }
+            None if rest.len() > 1 && rest.starts_with('^') => {
+                modifiers |= KeyModifiers::CONTROL;
+                rest = &rest[1..];
+            }
+            None => break,
+        }
+    }
+    let mut chars = rest.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(Key::new(KeyCode::Char(c), modifiers));
+    }
+    let lower = rest.to_lowercase();
+    let code = match lower.strip_prefix('f').and_then(|n| n.parse().ok()) {
+        Some(n @ 1..=12) => KeyCode::F(n),
+        _ => KEYS.iter().find(|(n, _)| *n == lower)?.1,
+    };
+    Some(Key::new(code, modifiers))
+}
+
+const MODIFIERS: [(KeyModifiers, &str); 3] = [
+    (KeyModifiers::CONTROL, "ctrl+"),
+    (KeyModifiers::ALT, "alt+"),
+    (KeyModifiers::SHIFT, "shift+"),
+];
+
+// the keys that go by a name rather than the character they type
+const KEYS: [(&str, KeyCode); 15] = [
+    ("space", KeyCode::Char(' ')),
+    ("enter", KeyCode::Enter),
+    ("esc", KeyCode::Esc),
+    ("tab", KeyCode::Tab),
+    ("backspace", KeyCode::Backspace),
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("pageup", KeyCode::PageUp),
+    ("pagedown", KeyCode::PageDown),
+    ("insert", KeyCode::Insert),
+    ("delete", KeyCode::Delete),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn color_keys_can_be_moved() {
+        assert_eq!(
+            Action::from_name("launch_color_3"),
+            Some(Action::LaunchColor(2))
+        );
+        assert_eq!(Action::from_name("launch_color_0"), None);
+        assert_eq!(Action::from_name("launch_color_x"), None);
+        let mut keymap = Keymap::new(5);
+        let red = parse_key("r").unwrap();
+        keymap.bind(Action::from_name("launch_color_1").unwrap(), &[red]);
+        assert_eq!(keymap.resolve(red), Some(Action::LaunchColor(0)));
+        assert_eq!(keymap.resolve(KeyCode::Char('1').into()), None);
+        assert_eq!(
+            keymap.resolve(KeyCode::Char('2').into()),
+            Some(Action::LaunchColor(1))
+        );
+    }
+}
@@ -0,0 +1,3 @@
+pub mod sim;
+#[cfg(feature = "web")]
+pub mod web;
@@ -0,0 +1,82 @@
+use std::{
+    fs::File,
+    io::{self, LineWriter},
+    path::Path,
+    sync::Mutex,
+};
+use tracing::{level_filters::LevelFilter, Subscriber};
+use tracing_subscriber::fmt::{time::Uptime, MakeWriter};
+
+/// Sends everything logged from here on to `path`, as the terminal is taken
+/// by the show: down to the debug lines with `debug`, and only from info up
+/// without.
+pub fn open(path: &Path, debug: bool) -> io::Result<()> {
+    let file = Mutex::new(LineWriter::new(File::create(path)?));
+    // only ever called once, before anything is logged
+    let _ = tracing::subscriber::set_global_default(subscriber(file, debug));
+    Ok(())
+}
+
+// lines like
+// `  1.234s DEBUG step{tick=120}:launch{color=2}: firewors::sim::world: launched a peony`,
+// with how long since starting and the spans they were logged in
+fn subscriber<W>(out: W, debug: bool) -> impl Subscriber + Send + Sync
+where
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let level = match debug {
+        true => LevelFilter::DEBUG,
+        false => LevelFilter::INFO,
+    };
+    tracing_subscriber::fmt()
+        .with_writer(out)
+        .with_ansi(false)
+        .with_timer(Uptime::default())
+        .with_max_level(level)
+        .finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::Write,
+        sync::{Arc, Mutex},
+    };
+    use tracing::{debug, debug_span};
+
+    #[derive(Clone, Default)]
+    struct Lines(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Lines {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn lines_say_when_and_where() {
+        let lines = Lines::default();
+        let out = lines.clone();
+        let log = |debug| {
+            let out = out.clone();
+            tracing::subscriber::with_default(subscriber(move || out.clone(), debug), || {
+                debug_span!("step", tick = 3).in_scope(|| debug!(target: "tick", "12 particles"))
+            })
+        };
+        log(false);
+        assert!(lines.0.lock().unwrap().is_empty());
+        log(true);
+        let text = String::from_utf8(lines.0.lock().unwrap().clone()).unwrap();
+        assert!(text.trim_start().starts_with("0."), "{}", text);
+        assert!(
+            text.ends_with("s DEBUG step{tick=3}: tick: 12 particles\n"),
+            "{}",
+            text
+        );
+    }
+}
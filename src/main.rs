@@ -1,347 +1,1739 @@
-use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
-    execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-};
-use rand::{prelude::ThreadRng, Rng};
-use std::{
-    error::Error,
-    ops::{Add, Mul, Sub},
+mod aim;
+mod ansi;
+mod args;
+mod audio;
+mod auto;
+mod backend;
+mod bench;
+mod budget;
+mod cast;
+mod config;
+mod control;
+mod countdown;
+mod demo;
+mod error;
+mod export;
+mod gif;
+mod keymap;
+mod logging;
+mod net;
+mod pacing;
+mod paint;
+mod png;
+mod record;
+mod scenery;
+mod schedule;
+mod screenshot;
+mod show;
+#[cfg(feature = "sound")]
+mod sound;
+mod stats;
+#[cfg(test)]
+mod tests;
+mod trigger;
+
+use crossterm::event::{Event, MouseButton, MouseEventKind};
+use firewors::sim::{self, BurstType, Effect, FountainEmitter, Ground, Kind, Vector, Well, World};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use ratatui::{
+    backend::{Backend, TestBackend},
+    buffer::Buffer,
+    layout::{Alignment, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Widget, Wrap},
+    Frame, Terminal,
 };
 use std::{
     io,
-    time::{Duration, Instant},
-};
-use tui::{
-    backend::{Backend, CrosstermBackend},
-    layout::{Constraint, Direction, Layout},
-    style::Color,
-    widgets::{
-        canvas::{Canvas, Points},
-        Block,
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
     },
-    Frame, Terminal,
+    thread,
+    time::{Duration, Instant, SystemTime},
 };
+use tracing::{debug, debug_span, error, info};
+
+use aim::{Launcher, Sight};
+use ansi::Depth;
+use args::{Args, Command};
+use audio::Audio;
+use auto::AutoShow;
+use backend::Term;
+use budget::Budget;
+use cast::{Cast, Tee};
+use clap::Parser;
+use config::{built_in_theme, config_path, parse_color, Config, Preset};
+use control::Control;
+use countdown::{clock, BigClock, Countdown};
+use demo::{Cue, Demo};
+use error::Error;
+use keymap::{key_name, Action, Keymap};
+use net::{Client, Host, News, Rocket};
+use pacing::Envelope;
+use paint::{Glyph, Glyphs};
+use record::{Input, Recorder, Replay};
+use scenery::{Backdrop, Layers, Scenery};
+use schedule::{Launch, Schedule};
+use show::Show;
+#[cfg(feature = "sound")]
+use sound::Sound;
+use stats::Summary;
+use trigger::Trigger;
+
+struct App {
+    sim: World,
+    rng: StdRng,
+    // the palette it started with, which the demo comes back to
+    palette: Vec<Color>,
+    keymap: Keymap,
+    paused: bool,
+    // how much faster than real time the simulation runs
+    time_scale: f64,
+    hud: bool,
+    // drawn with the glyphs picked for each kind of particle, or with the
+    // plainer one `b` switches to
+    braille: bool,
+    glyphs: Glyphs,
+    plain: Glyphs,
+    // solid blocks in bold colors, leaving out those too dim to make out
+    high_contrast: bool,
+    // share of the usual embers and launches, fewer with --reduced-motion
+    motion: f64,
+    // how many colors the terminal draws with
+    depth: Depth,
+    fps: f64,
+    // multiplies the speed rockets leave the ground at
+    launch_speed: f64,
+    // what message bursts spell out
+    message: Option<String>,
+    // rows of water at the bottom of the screen, 0 for none
+    reflection: u16,
+    // frames drawn, which the ripples go by
+    frames: u64,
+    recorder: Option<Recorder>,
+    replay: Option<Replay>,
+    auto: Option<AutoShow>,
+    pacing: Envelope,
+    show: Option<Show>,
+    demo: Option<Demo>,
+    // what the demo is showing, under the fireworks
+    caption: Option<String>,
+    countdown: Option<Countdown>,
+    triggers: Vec<Trigger>,
+    audio: Option<Audio>,
+    #[cfg(feature = "sound")]
+    sound: Option<Sound>,
+    control: Option<Control>,
+    host: Option<Host>,
+    client: Option<Client>,
+    schedule: Schedule,
+    // quit on our own once this passes
+    deadline: Option<Instant>,
+    // quit was asked for, rather than the show ending by itself
+    quit: bool,
+    scenery: Scenery,
+    // a setting that was just changed, shown until the time runs out
+    notice: Option<(String, Instant)>,
+    // the next frame drawn is saved to the working directory
+    screenshot: bool,
+    wrap: bool,
+    // gravity wells from the config file, placed as a share of the screen
+    wells: Vec<Well>,
+    // the launcher the arrow keys steer while aiming
+    aim: Option<Launcher>,
+    // particles alive at once in each color before the budget, which
+    // follows the screen size and how quickly frames get drawn
+    particles: usize,
+    budget: Budget,
+}
 
-#[derive(Debug, Copy, Clone, PartialEq)]
-struct Vector {
-    x: f64,
-    y: f64,
+// the terminal as the simulation sees it, in canvas units; with a
+// reflection that is only the sky above the water
+struct Screen {
+    size: Rect,
+    width: f64,
+    height: f64,
+    max_speed: f64,
 }
 
-impl Vector {
-    fn zero() -> Vector {
-        Vector { x: 0.0, y: 0.0 }
+impl Screen {
+    fn new(size: Rect, reflection: u16) -> Screen {
+        let height = f64::from(sky(size, reflection).height);
+        Screen {
+            size,
+            width: f64::from(size.width),
+            height,
+            max_speed: 8.0 * height.powf(0.5),
+        }
+    }
+
+    // a rocket as a share of the screen size and the top launch speed, the
+    // way it goes over the network
+    fn to_rocket(&self, color: usize, pos: Vector, vel: Vector, burst: BurstType) -> Rocket {
+        Rocket {
+            color,
+            pos: Vector {
+                x: pos.x / self.width,
+                y: pos.y / self.height,
+            },
+            vel: vel * (1.0 / self.max_speed),
+            burst,
+        }
+    }
+
+    fn place_rocket(&self, rocket: Rocket) -> (Vector, Vector) {
+        let pos = Vector {
+            x: rocket.pos.x * self.width,
+            y: rocket.pos.y * self.height,
+        };
+        (pos, rocket.vel * self.max_speed)
+    }
+
+    // canvas coordinates have the origin at the center of the screen and y
+    // growing upwards
+    fn to_canvas(&self, column: u16, row: u16) -> Vector {
+        Vector {
+            x: f64::from(column) + 0.5 - self.width / 2.0,
+            y: self.height / 2.0 - f64::from(row) - 0.5,
+        }
     }
 }
 
-impl Add for Vector {
-    type Output = Self;
+impl App {
+    fn new(config: Config, args: &Args) -> App {
+        let mut sim = World::with_palette(&config.palette);
+        if let Some(strength) = args.gravity {
+            sim.set_gravity(strength);
+        }
+        if let Some(scale) = args.drag {
+            sim.set_drag(scale);
+        }
+        if let Some(strength) = args.wind {
+            sim.set_wind(strength);
+        }
+        if let Some(strength) = args.turbulence {
+            sim.set_turbulence(strength);
+        }
+        if let Some(n) = args.particles {
+            sim.set_max_particles(n as usize);
+        }
+        if let Some(n) = args.subparticles {
+            sim.set_burst_particles(n as usize);
+        }
+        if let Some(scale) = args.burst_scale {
+            sim.set_burst_scale(scale);
+        }
+        if let Some([min, max]) = args.shell_sizes {
+            sim.set_shell_sizes(min, max);
+        }
+        if let Some(speed) = args.apex_fuzz {
+            sim.set_apex_fuzz(speed);
+        }
+        if let Some(depth) = args.depth {
+            sim.set_depth(depth);
+        }
+        if let Some(ground) = args.ground {
+            sim.set_ground(ground);
+        }
+        if args.smoke {
+            sim.toggle_smoke();
+        }
+        sim.set_flashing(!args.reduced_motion);
+        let rng = match args.seed {
+            Some(seed) => {
+                sim.set_seed(seed);
+                // launches draw from their own stream so they don't shift
+                // the explosions around
+                StdRng::seed_from_u64(seed.wrapping_add(1))
+            }
+            None => StdRng::from_entropy(),
+        };
+        // frame times differ from run to run, so a recording or a replay
+        // only goes by the screen size
+        let frames = args.record.is_none() && args.replay.is_none();
+        App {
+            particles: sim.max_particles(),
+            budget: Budget::new(!args.no_adapt, frames && !args.no_adapt),
+            sim,
+            rng,
+            palette: config.palette,
+            keymap: config.keymap,
+            paused: false,
+            time_scale: match args.reduced_motion {
+                true => REDUCED_SPEED,
+                false => 1.0,
+            },
+            hud: false,
+            braille: !args.high_contrast || args.ascii,
+            glyphs: config.glyphs.over(match args.ascii {
+                true => Glyphs::ASCII,
+                false => Glyphs::BRAILLE,
+            }),
+            plain: Glyphs::all(match (args.ascii, args.high_contrast) {
+                (true, _) => Glyph::Char('*'),
+                (false, true) => Glyph::Block,
+                (false, false) => Glyph::Char('•'),
+            }),
+            high_contrast: args.high_contrast,
+            motion: match args.reduced_motion {
+                true => REDUCED_MOTION,
+                false => 1.0,
+            },
+            depth: args.colors.depth(),
+            fps: 0.0,
+            launch_speed: args.launch_speed.unwrap_or(1.0),
+            message: args.message.clone(),
+            reflection: args.reflection.unwrap_or(0),
+            frames: 0,
+            recorder: None,
+            replay: None,
+            auto: None,
+            pacing: config.pacing,
+            show: None,
+            demo: None,
+            caption: None,
+            countdown: None,
+            triggers: config.triggers,
+            audio: None,
+            #[cfg(feature = "sound")]
+            sound: None,
+            control: None,
+            host: None,
+            client: None,
+            schedule: Schedule::new(tick_secs(args)),
+            deadline: None,
+            quit: false,
+            scenery: Scenery::new(match args.scenery {
+                true => Layers::all(),
+                false => config.scenery,
+            }),
+            notice: None,
+            screenshot: false,
+            wrap: args.wrap,
+            wells: config.wells,
+            aim: args.aim.then(Launcher::new),
+        }
+    }
+
+    fn apply(&mut self, input: Input, screen: &Screen) {
+        let (w_float, h_float, max_speed) = (screen.width, screen.height, screen.max_speed);
+        match input {
+            Input::Action(Action::Quit) => {}
+            Input::Action(Action::MultiLaunch) => {
+                for i in -10..10 {
+                    let pos_x = f64::from(i) * w_float / 20.0;
+                    let color = self.rng.gen_range(0..self.sim.palette().len());
+                    self.launch(color, pos_x, -h_float / 2.0, max_speed);
+                }
+            }
+            Input::Action(Action::Launch) => {
+                let pos_x = self.rng.gen_range(-w_float / 2.0..w_float / 2.0);
+                let color = self.rng.gen_range(0..self.sim.palette().len());
+                self.launch(color, pos_x, -h_float / 2.0, max_speed);
+            }
+            Input::Action(Action::LaunchColor(color)) => {
+                // a hand-edited log may name a color the palette doesn't have
+                if color < self.sim.palette().len() {
+                    let pos_x = self.rng.gen_range(-w_float / 2.0..w_float / 2.0);
+                    self.launch(color, pos_x, -h_float / 2.0, max_speed);
+                }
+            }
+            Input::Action(Action::LaunchShape(burst)) => {
+                let launch = Launch {
+                    x: None,
+                    color: None,
+                    burst: Some(burst),
+                    speed: None,
+                };
+                self.launch_planned(launch, screen);
+            }
+            Input::Action(Action::Pause) => {
+                self.paused = !self.paused;
+            }
+            Input::Action(Action::CycleShape) => {
+                self.sim.cycle_burst();
+            }
+            Input::Action(Action::CycleDepth) => {
+                self.sim.cycle_depth();
+            }
+            Input::Action(Action::CycleGround) => {
+                self.sim.cycle_ground();
+                let text = match self.sim.ground() {
+                    Ground::FallThrough => "embers fall through the ground",
+                    Ground::Bounce => "embers bounce off the ground",
+                    Ground::Slide => "embers slide along the ground",
+                };
+                self.notify(text.into());
+            }
+            Input::Action(Action::ToggleHud) => {
+                self.hud = !self.hud;
+            }
+            Input::Action(Action::ToggleBraille) => {
+                self.braille = !self.braille;
+            }
+            Input::Action(Action::CycleFountains) => {
+                self.cycle_fountains(w_float, h_float);
+            }
+            Input::Action(Action::ToggleTrails) => {
+                self.sim.toggle_trails();
+            }
+            Input::Action(Action::ToggleSmoke) => {
+                self.sim.toggle_smoke();
+            }
+            Input::Action(Action::SlowDown) => {
+                self.time_scale = (self.time_scale / 2.0).max(TIME_SCALES[0]);
+            }
+            Input::Action(Action::Finale) => {
+                self.finale();
+            }
+            Input::Action(Action::LaunchMessage) => {
+                self.launch_planned(message_launch(), screen);
+            }
+            Input::Action(Action::SpeedUp) => {
+                self.time_scale = (self.time_scale * 2.0).min(TIME_SCALES[1]);
+            }
+            Input::Action(Action::ToggleAim) => {
+                self.aim = match self.aim {
+                    Some(_) => None,
+                    None => Some(Launcher::new()),
+                };
+            }
+            // while aiming the arrows steer the launcher instead
+            Input::Action(action @ (Action::MoreTurbulence | Action::LessTurbulence))
+                if self.aim.is_some() =>
+            {
+                let step = match action {
+                    Action::MoreTurbulence => POWER_STEP,
+                    _ => -POWER_STEP,
+                };
+                self.aim_with(|launcher| launcher.push(step));
+            }
+            Input::Action(action @ (Action::WindLeft | Action::WindRight))
+                if self.aim.is_some() =>
+            {
+                let step = match action {
+                    Action::WindLeft => -AIM_STEP,
+                    _ => AIM_STEP,
+                };
+                if let Some(launcher) = self.aim.as_mut() {
+                    launcher.nudge(step);
+                }
+            }
+            Input::Action(action @ (Action::TiltLeft | Action::TiltRight)) => {
+                let step = match action {
+                    Action::TiltLeft => -TILT_STEP,
+                    _ => TILT_STEP,
+                };
+                self.aim_with(|launcher| launcher.tilt(step));
+            }
+            Input::Action(Action::Fire) => {
+                if let Some(launcher) = self.aim {
+                    let color = self.rng.gen_range(0..self.sim.palette().len());
+                    let pos = Vector {
+                        x: launcher.x * w_float,
+                        y: -h_float / 2.0,
+                    };
+                    let vel = launcher.velocity(max_speed * self.launch_speed);
+                    self.sim.launch(color, pos, vel);
+                }
+            }
+            Input::Action(Action::WindLeft) => {
+                let wind = self.sim.wind() - WIND_STEP;
+                self.sim.set_wind(wind.max(-MAX_WIND));
+            }
+            Input::Action(Action::WindRight) => {
+                let wind = self.sim.wind() + WIND_STEP;
+                self.sim.set_wind(wind.min(MAX_WIND));
+            }
+            Input::Action(Action::MoreTurbulence) => {
+                let turbulence = self.sim.turbulence() + TURBULENCE_STEP;
+                self.sim.set_turbulence(turbulence.min(MAX_TURBULENCE));
+            }
+            Input::Action(Action::LessTurbulence) => {
+                let turbulence = self.sim.turbulence() - TURBULENCE_STEP;
+                self.sim.set_turbulence(turbulence.max(0.0));
+            }
+            Input::Action(Action::MoreGravity) => {
+                let gravity = -self.sim.gravity().y + GRAVITY_STEP;
+                self.sim.set_gravity(gravity.min(MAX_GRAVITY));
+                self.notify(format!("gravity {:.0}", -self.sim.gravity().y));
+            }
+            Input::Action(Action::LessGravity) => {
+                let gravity = -self.sim.gravity().y - GRAVITY_STEP;
+                self.sim.set_gravity(gravity.max(0.0));
+                self.notify(format!("gravity {:.0}", -self.sim.gravity().y));
+            }
+            Input::Action(Action::MoreDrag) => {
+                self.sim
+                    .set_drag((self.sim.drag() + DRAG_STEP).min(MAX_DRAG));
+                self.notify(format!("drag {:.2}x", self.sim.drag()));
+            }
+            Input::Action(Action::LessDrag) => {
+                self.sim.set_drag(self.sim.drag() - DRAG_STEP);
+                self.notify(format!("drag {:.2}x", self.sim.drag()));
+            }
+            Input::Action(Action::MoreSparks) => {
+                let sparks = self.burst_particles() + SPARKS_STEP;
+                self.sim.set_burst_particles(sparks.min(MAX_SPARKS));
+                self.notify(format!("sparks {}", self.burst_particles()));
+            }
+            Input::Action(Action::FewerSparks) => {
+                let sparks = self.burst_particles().saturating_sub(SPARKS_STEP);
+                self.sim.set_burst_particles(sparks.max(SPARKS_STEP));
+                self.notify(format!("sparks {}", self.burst_particles()));
+            }
+            #[cfg(feature = "sound")]
+            Input::Action(Action::ToggleSound) => {
+                if let Some(sound) = self.sound.as_mut() {
+                    sound.toggle_mute();
+                }
+            }
+            Input::Action(Action::Screenshot) => {
+                self.screenshot = true;
+            }
+            Input::Attract { column, row } => {
+                self.sim.set_attractor(Some(screen.to_canvas(column, row)));
+            }
+            Input::Release => {
+                self.sim.set_attractor(None);
+            }
+            Input::LaunchAt { column, row } => {
+                let color = self.rng.gen_range(0..self.sim.palette().len());
+                self.launch_at(color, screen.to_canvas(column, row), screen);
+            }
+            Input::Launch(mut launch) => {
+                // a hand-edited log may name a color the palette doesn't have
+                launch.color = launch.color.filter(|c| *c < self.sim.palette().len());
+                self.launch_planned(launch, screen);
+            }
+            Input::Theme(name) => {
+                let palette = match name.and_then(|name| built_in_theme(name, self.depth)) {
+                    Some(colors) => colors.iter().filter_map(|c| parse_color(c)).collect(),
+                    None => self.palette.clone(),
+                };
+                self.sim.set_palette(&palette);
+            }
+            // the session follows the screen itself
+            Input::Resize { .. } => {}
+            Input::Beat { strength } => {
+                // louder beats climb faster, so they burst higher and bigger
+                let launch = Launch {
+                    x: None,
+                    color: None,
+                    burst: None,
+                    speed: Some(0.6 + 0.4 * f64::from(strength.min(100)) / 100.0),
+                };
+                self.launch_planned(launch, screen);
+            }
+        }
+    }
+
+    fn launch(&mut self, color: usize, x: f64, y: f64, max_speed: f64) {
+        let vel = self.rocket_velocity(max_speed);
+        self.sim.launch(color, Vector { x, y }, vel);
+    }
+
+    // rockets burst just past their apex, so aim for it to be at `target`
+    fn launch_at(&mut self, color: usize, target: Vector, screen: &Screen) {
+        let gravity = -self.sim.gravity().y;
+        let ground = -screen.height / 2.0;
+        if gravity <= 0.0 {
+            self.launch(color, target.x, ground, screen.max_speed);
+            return;
+        }
+        let speed_y = (2.0 * gravity * (target.y - ground).max(1.0)).sqrt();
+        let speed_x = self.rng.gen_range(-8.0..8.0);
+        let flight = speed_y / gravity;
+        self.sim.launch(
+            color,
+            Vector {
+                x: target.x - speed_x * flight,
+                y: ground,
+            },
+            Vector {
+                x: speed_x,
+                y: speed_y,
+            },
+        );
+    }
+
+    // embers per burst, the peony's number until somebody picks one
+    fn burst_particles(&self) -> usize {
+        self.sim
+            .burst_particles()
+            .unwrap_or_else(|| BurstType::Peony.particle_count())
+    }
+
+    fn notify(&mut self, text: String) {
+        debug!("notice: {}", text);
+        self.notice = Some((text, Instant::now() + NOTICE_TIME));
+    }
+
+    // changes the aim, if aiming, and shows what it is now
+    fn aim_with(&mut self, change: impl FnOnce(&mut Launcher)) {
+        if let Some(launcher) = self.aim.as_mut() {
+            change(launcher);
+            let text = format!(
+                "angle {:+.0}°  power {:.0}%",
+                launcher.angle,
+                launcher.power * 100.0
+            );
+            self.notify(text);
+        }
+    }
+
+    fn rocket_velocity(&mut self, max_speed: f64) -> Vector {
+        let max_speed = max_speed * self.launch_speed;
+        let speed_y = self.rng.gen_range(max_speed * 0.8..max_speed);
+        let speed_x = self.rng.gen_range(-8.0..8.0);
+        Vector {
+            x: speed_x,
+            y: speed_y,
+        }
+    }
+
+    // whatever the plan leaves out is picked like for a key press
+    fn launch_planned(&mut self, launch: Launch, screen: &Screen) {
+        let x = match launch.x {
+            Some(x) => x * screen.width,
+            None => self.rng.gen_range(-screen.width / 2.0..screen.width / 2.0),
+        };
+        let color = match launch.color {
+            Some(color) => color,
+            None => self.rng.gen_range(0..self.sim.palette().len()),
+        };
+        let pos = Vector {
+            x,
+            y: -screen.height / 2.0,
+        };
+        let vel = self.rocket_velocity(screen.max_speed * launch.speed.unwrap_or(1.0));
+        match launch.burst {
+            Some(burst) => self.sim.launch_burst(color, pos, vel, burst),
+            None => self.sim.launch(color, pos, vel),
+        }
+    }
+
+    // waves of rockets across the whole width, coming quicker, bigger and
+    // higher, then one last salvo of the showiest bursts
+    fn finale(&mut self) {
+        let mut t = 0.0;
+        for wave in 0..FINALE_WAVES {
+            let progress = f64::from(wave) / f64::from(FINALE_WAVES - 1);
+            let rockets = 3 + (progress * 7.0) as u32;
+            for i in 0..rockets {
+                let x = (f64::from(i) + self.rng.gen_range(0.2..0.8)) / f64::from(rockets) - 0.5;
+                let launch = Launch {
+                    x: Some(x),
+                    color: None,
+                    burst: None,
+                    speed: Some(0.8 + 0.2 * progress),
+                };
+                let delay = t + self.rng.gen_range(0.0..0.15);
+                self.schedule.push(delay, launch);
+            }
+            t += 0.7 - 0.4 * progress;
+        }
+        for i in 0..12 {
+            let burst = match i % 3 {
+                0 => BurstType::Crossette,
+                1 => BurstType::Chrysanthemum,
+                _ => BurstType::Willow,
+            };
+            let launch = Launch {
+                x: Some(f64::from(i) / 11.0 * 0.9 - 0.45),
+                color: None,
+                burst: Some(burst),
+                speed: Some(1.0),
+            };
+            self.schedule.push(t + 0.5, launch);
+        }
+        if self.sim.has_message() {
+            self.schedule.push(t + 1.5, message_launch());
+        }
+    }
+
+    // the ground moves with the bottom of the window, and fountains keep
+    // their place across it
+    fn resize(&mut self, old: &Screen, new: &Screen) {
+        self.fit_edges(new);
+        self.fit_message(new);
+        self.fit_budget(new.size);
+        let fountains = self.sim.fountains().to_vec();
+        self.sim.clear_fountains();
+        for fountain in fountains {
+            self.sim.add_fountain(FountainEmitter {
+                pos: Vector {
+                    x: fountain.pos.x * new.width / old.width,
+                    y: -new.height / 2.0,
+                },
+                ..fountain
+            });
+        }
+    }
 
-    fn add(self, other: Self) -> Self {
-        Self {
-            x: self.x + other.x,
-            y: self.y + other.y,
+    // the floor at the bottom of the sky, and the sides particles wrap
+    // around with --wrap
+    fn fit_edges(&mut self, screen: &Screen) {
+        self.sim.set_floor(-screen.height / 2.0);
+        self.sim.set_wrap(self.wrap.then_some(screen.width));
+        self.sim.clear_wells();
+        for well in self.wells.iter() {
+            let pos = Vector {
+                x: well.pos.x * screen.width,
+                y: well.pos.y * screen.height,
+            };
+            self.sim.add_well(Well { pos, ..*well });
+        }
+    }
+
+    // more of everything on a bigger screen
+    fn fit_budget(&mut self, size: Rect) {
+        self.budget.resize(size);
+        self.follow_budget();
+    }
+
+    fn follow_budget(&mut self) {
+        self.sim
+            .set_max_particles(self.budget.particles(self.particles));
+        let scale = self.budget.scale() * self.motion;
+        self.sim.set_density(scale);
+        if let Some(auto) = self.auto.as_mut() {
+            auto.set_rate(scale);
+        }
+    }
+
+    // backs off while frames take too long to draw, and builds back up
+    fn frame_took(&mut self, took: Duration) {
+        if self.budget.frame(took) {
+            self.follow_budget();
+        }
+    }
+
+    // lets the text span most of the screen, a quarter of it high
+    fn fit_message(&mut self, screen: &Screen) {
+        if let Some(text) = &self.message {
+            self.sim
+                .set_message(text, screen.width * 0.9, screen.height / 4.0);
+        }
+    }
+
+    // none, one in the middle, then a row of three
+    fn cycle_fountains(&mut self, width: f64, height: f64) {
+        let spots: &[f64] = match self.sim.fountains().len() {
+            0 => &[0.0],
+            1 => &[-0.3, 0.0, 0.3],
+            _ => &[],
+        };
+        self.sim.clear_fountains();
+        let colors = self.sim.palette().len();
+        for x in spots {
+            self.sim.add_fountain(FountainEmitter {
+                pos: Vector {
+                    x: x * width,
+                    y: -height / 2.0,
+                },
+                rate: 200.0,
+                spread: 6.0,
+                color_index: self.rng.gen_range(0..colors),
+            });
         }
     }
 }
 
-impl Sub for Vector {
-    type Output = Self;
+fn main() {
+    if let Err(err) = run() {
+        error!("{}", err);
+        eprintln!("firewors: {}", err);
+        process::exit(1);
+    }
+}
 
-    fn sub(self, other: Self) -> Self {
-        Self {
-            x: self.x - other.x,
-            y: self.y - other.y,
+fn run() -> Result<(), Error> {
+    let mut args = Args::parse();
+    let log = args
+        .log
+        .clone()
+        .or_else(|| args.debug.then(|| PathBuf::from(DEBUG_LOG)));
+    if let Some(path) = log {
+        logging::open(&path, args.debug).map_err(Error::open(path.display()))?;
+        info!(
+            "firewors {} started with {:?}",
+            env!("CARGO_PKG_VERSION"),
+            args
+        );
+    }
+    let config = Config::load(
+        &config_path(),
+        args.preset.as_deref(),
+        args.theme
+            .as_deref()
+            .or(args.high_contrast.then_some("contrast")),
+        args.colors.depth(),
+    )?;
+    let replay = replaying(&mut args)?;
+    args.fill_from(&config.preset);
+
+    let mut app = App::new(config, &args);
+    app.replay = replay;
+    match &args.command {
+        Some(Command::Bench { ticks, particles }) => {
+            bench::run(&mut app, *ticks, *particles);
+            Ok(())
         }
+        Some(Command::Export(export)) => export::run(app, &args, export),
+        None => run_terminal(app, &args),
     }
 }
 
-impl Mul<f64> for Vector {
-    type Output = Self;
+fn run_terminal(mut app: App, args: &Args) -> Result<(), Error> {
+    let size = backend::size(args.backend)?;
+    open_session(&mut app, args, size)?;
+    let cast = match &args.cast {
+        Some(path) => {
+            Some(Cast::create(path, args.backend, size).map_err(Error::open(path.display()))?)
+        }
+        None => None,
+    };
 
-    fn mul(self, scalar: f64) -> Self {
-        Self {
-            x: self.x * scalar,
-            y: self.y * scalar,
+    let mut term = backend::open(args.backend, Tee::new(io::stdout(), cast))?;
+    let tick_rate = Duration::from_secs_f64(tick_secs(args));
+    let session = run_app(term.as_mut(), app, tick_rate)?;
+    let summary = session.summary();
+    info!("{}", summary.json());
+    if let Some(path) = &args.stats_out {
+        summary.save(path).map_err(Error::open(path.display()))?;
+    }
+    if session.app.quit {
+        show_summary(term.as_mut(), &summary)?;
+    }
+    Ok(())
+}
+
+// how the show went, until a key is pressed or the time runs out
+fn show_summary(term: &mut dyn Term, summary: &Summary) -> io::Result<()> {
+    let lines = summary.lines();
+    let until = Instant::now() + SUMMARY_TIME;
+    loop {
+        term.draw(&mut |f| summary_ui(f, &lines))?;
+        let left = until.saturating_duration_since(Instant::now());
+        match term.poll(left)? {
+            // anything else, like a resize, only draws it again
+            Some(Event::Key(_)) | None => return Ok(()),
+            Some(_) => {}
         }
     }
 }
 
-fn random_unit_vector(rng: &mut ThreadRng) -> Vector {
-    let x: f64 = rng.gen_range(-1.0..1.0);
-    let y: f64 = rng.gen_range(-1.0..1.0);
-    let abs = (x.powi(2) + y.powi(2)).powf(0.5);
-    Vector {
-        x: x / abs,
-        y: y / abs,
+// replays come out the same with the seed and the settings they were
+// recorded with, which come before a preset's, so a recording always gets
+// a seed
+fn replaying(args: &mut Args) -> Result<Option<Replay>, Error> {
+    let replay = match &args.replay {
+        Some(path) => Some(Replay::load(path).map_err(Error::open(path.display()))?),
+        None => None,
+    };
+    if let Some(replay) = &replay {
+        args.seed = args.seed.or(replay.seed);
+        args.fill_from(&replay.settings);
+    }
+    if args.record.is_some() {
+        args.seed = args.seed.or_else(|| Some(rand::random()));
     }
+    Ok(replay)
 }
 
-#[derive(Copy, Clone, Debug)]
-struct Particle {
-    pos: Vector,
-    vel: Vector,
-    acc: Vector,
-    dont_delete: bool,
-    exploded: bool,
-    subparticle: bool,
+fn tick_secs(args: &Args) -> f64 {
+    Duration::from_millis(args.tick_ms.unwrap_or(10)).as_secs_f64()
 }
 
-impl Particle {
-    fn new(is_sub: bool, ipos: Vector, ivel: Vector) -> Particle {
-        Particle {
-            pos: ipos,
-            vel: ivel,
-            acc: Vector { x: 0.0, y: 0.0 },
-            dont_delete: true,
-            exploded: false,
-            subparticle: is_sub,
+fn auto_show(args: &Args, pacing: &Envelope) -> AutoShow {
+    let min = args.auto_min.unwrap_or(0.3);
+    let max = args.auto_max.unwrap_or(2.0).max(min);
+    let ramp = args.auto_ramp.unwrap_or(30.0);
+    AutoShow::new(min, max, ramp, pacing.clone(), tick_secs(args))
+}
+
+// sets up whatever feeds the session inputs, for a screen of `size` cells
+fn open_session(app: &mut App, args: &Args, size: (u16, u16)) -> Result<(), Error> {
+    if let Some((width, height)) = app.replay.as_ref().and_then(|r| r.size) {
+        if size != (width, height) {
+            eprintln!(
+                "firewors: the replay was recorded at {}x{}, it may not fit on screen",
+                width, height
+            );
         }
     }
+    if let Some(path) = &args.record {
+        let settings = Preset {
+            tick_ms: Some(args.tick_ms.unwrap_or(10)),
+            gravity: Some(-app.sim.gravity().y),
+            drag: Some(app.sim.drag()),
+            wind: Some(app.sim.wind()),
+            turbulence: Some(app.sim.turbulence()),
+            particles: args.particles,
+            subparticles: args.subparticles,
+            launch_speed: Some(app.launch_speed),
+            burst_scale: args.burst_scale,
+            shell_sizes: args.shell_sizes,
+            apex_fuzz: args.apex_fuzz,
+            depth: args.depth,
+            ground: Some(app.sim.ground()),
+            wrap: Some(app.wrap),
+            ..Preset::default()
+        };
+        let recorder = Recorder::create(path, args.seed, size, &settings);
+        app.recorder = Some(recorder.map_err(Error::open(path.display()))?);
+    }
+    // a replay already has the launches the show made when it was recorded
+    if args.auto && app.replay.is_none() {
+        app.auto = Some(auto_show(args, &app.pacing));
+    }
+    // as is the finale the countdown set off, and what the triggers did
+    if app.replay.is_none() {
+        if let Some(time) = &args.countdown {
+            app.countdown = Some(Countdown::parse(time).map_err(Error::Countdown)?);
+        } else if args.midnight {
+            app.countdown = Some(Countdown::midnight());
+        }
+    } else {
+        app.triggers.clear();
+    }
+    if let Some(source) = args.audio.as_ref().filter(|_| app.replay.is_none()) {
+        let audio = match source {
+            Some(path) => {
+                Audio::open(path, args.audio_rate).map_err(Error::open(path.display()))?
+            }
+            #[cfg(feature = "audio")]
+            None => Audio::listen().map_err(Error::open("the audio input"))?,
+            // --audio takes a path without the feature
+            #[cfg(not(feature = "audio"))]
+            None => unreachable!(),
+        };
+        app.audio = Some(audio);
+    }
+    if let Some(path) = args.control.as_ref().filter(|_| app.replay.is_none()) {
+        let control =
+            Control::open(path, app.sim.palette()).map_err(Error::open(path.display()))?;
+        app.control = Some(control);
+    }
+    if let Some(addr) = &args.host {
+        let host = Host::listen(addr, app.message.clone()).map_err(Error::open(addr))?;
+        app.sim.listen();
+        app.host = Some(host);
+    }
+    if let Some(addr) = &args.join {
+        let client = Client::connect(addr).map_err(Error::open(addr))?;
+        app.client = Some(client);
+    }
+    #[cfg(feature = "sound")]
+    if args.sound {
+        let sound = Sound::open(args.volume).map_err(Error::open("the audio output"))?;
+        app.sim.listen();
+        app.sound = Some(sound);
+    }
+    // and everything a show file or the demo did
+    if let Some(path) = args.show.as_ref().filter(|_| app.replay.is_none()) {
+        app.show = Some(Show::load(path, &app.sim.palette(), tick_secs(args))?);
+    }
+    if let Some(secs) = args.demo.filter(|_| app.replay.is_none()) {
+        let rng = match args.seed {
+            Some(seed) => StdRng::seed_from_u64(seed.wrapping_add(2)),
+            None => StdRng::from_entropy(),
+        };
+        app.demo = Some(Demo::new(secs, tick_secs(args), rng));
+    }
+    if let Some(secs) = args.cast_duration {
+        app.deadline = Some(Instant::now() + Duration::from_secs_f64(secs));
+    }
+    Ok(())
+}
 
-    fn apply_force(&mut self, force: Vector) {
-        self.acc = self.acc + force;
+// runs the show until it ends, handing back the session it ended with
+fn run_app(term: &mut dyn Term, app: App, tick_rate: Duration) -> Result<Session, Error> {
+    let session = Arc::new(Mutex::new(Session::new(app, term.size()?.into())?));
+    let done = Arc::new(AtomicBool::new(false));
+    let physics = {
+        let (session, done) = (Arc::clone(&session), Arc::clone(&done));
+        thread::spawn(move || {
+            // set on the way out however it goes, so drawing stops too
+            let _done = Done(&done);
+            simulate(&session, &done, tick_rate)
+        })
+    };
+    let res = render(term, &session, &done);
+    done.store(true, Ordering::Relaxed);
+    let simulated = physics.join().map_err(|_| Error::Crashed)?;
+    res.and(simulated.map_err(Error::from))?;
+    let session = Arc::try_unwrap(session).map_err(|_| Error::Crashed)?;
+    session.into_inner().map_err(|_| Error::Crashed)
+}
+
+struct Done<'a>(&'a AtomicBool);
+
+impl Drop for Done<'_> {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
     }
+}
 
-    fn update(&mut self) {
-        if (!self.dont_delete || self.vel.y <= -0.05) && !self.subparticle {
-            self.dont_delete = false;
-            return;
-        }
-        self.vel = self.vel + self.acc;
-        self.pos = self.pos + self.vel;
-        self.acc = self.acc * 0.0;
-        if self.subparticle {
-            self.vel = self.vel * 0.98;
+// steps the session a tick at a time on a thread of its own, so the show
+// runs at the same speed however long a frame takes to draw, until it is
+// over or the drawing side is done
+fn simulate(session: &Mutex<Session>, done: &AtomicBool, tick_rate: Duration) -> io::Result<()> {
+    let mut next_tick = Instant::now() + tick_rate;
+    while !done.load(Ordering::Relaxed) {
+        thread::sleep(next_tick.saturating_duration_since(Instant::now()));
+        let mut session = session.lock().unwrap();
+        let mut steps = 0;
+        while next_tick <= Instant::now() {
+            next_tick += tick_rate;
+            // a pause still takes what is lined up, it only stops the clock
+            let going = match session.app.paused || session.small {
+                true => session.scheduled()?,
+                false => session.step(tick_rate.as_secs_f64())?,
+            };
+            if !going {
+                return Ok(());
+            }
+            steps += 1;
+            if steps == MAX_CATCH_UP {
+                // hopelessly behind; slow down rather than never let go
+                next_tick = Instant::now() + tick_rate;
+            }
         }
     }
+    Ok(())
 }
 
-const COLORS: [Color; 6] = [
-    Color::Blue,
-    Color::Green,
-    Color::Magenta,
-    Color::Red,
-    Color::Yellow,
-    Color::White,
-];
-const MAX_PARTICLES_COLOR: usize = 1000;
-
-#[derive(Copy, Clone, Debug)]
-struct ParticleGroup {
-    pos: [(f64, f64); MAX_PARTICLES_COLOR],
-    add_at: usize,
-    particles: [Particle; MAX_PARTICLES_COLOR],
-    color: Color,
-}
-
-impl ParticleGroup {
-    fn new(color: Color) -> ParticleGroup {
-        ParticleGroup {
-            pos: [(-9999.9, -9999.9); MAX_PARTICLES_COLOR],
-            add_at: 0,
-            particles: [Particle::new(
-                false,
-                Vector {
-                    x: -999.9,
-                    y: -999.9,
-                },
-                Vector::zero(),
-            ); MAX_PARTICLES_COLOR],
-            color: color,
+// draws frames and takes the keyboard and the mouse until either side is
+// done; frames are put together off screen, so the simulation only waits
+// for that and not for the terminal
+fn render(term: &mut dyn Term, session: &Mutex<Session>, done: &AtomicBool) -> Result<(), Error> {
+    // poisoned by a panic on the simulation side
+    let lock = || session.lock().map_err(|_| Error::Crashed);
+    let mut offscreen = Terminal::new(TestBackend::new(1, 1))?;
+    let mut last_draw = Instant::now();
+    let mut took = Duration::ZERO;
+    while !done.load(Ordering::Relaxed) {
+        let started = Instant::now();
+        let size = term.size()?;
+        if offscreen.size()? != size {
+            offscreen.backend_mut().resize(size.width, size.height);
+        }
+        let frame = {
+            let mut session = lock()?;
+            if session
+                .app
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+            {
+                return Ok(());
+            }
+            let frame_time = last_draw.elapsed().as_secs_f64();
+            last_draw = Instant::now();
+            if frame_time > 0.0 {
+                // smooth it out so the number is readable
+                session.app.fps = session.app.fps * 0.95 + 0.05 / frame_time;
+            }
+            session.app.frame_took(took);
+            session.draw(&mut offscreen)?;
+            offscreen.backend().buffer().clone()
+        };
+        let mut frame = Some(frame);
+        term.draw(&mut |f| {
+            if let Some(frame) = frame.take() {
+                f.render_widget(Drawn(frame), f.area())
+            }
+        })?;
+        took = started.elapsed();
+        debug!(target: "draw", "frame took {:.3}ms", took.as_secs_f64() * 1000.0);
+
+        // input as it comes in until the next frame is due
+        let timeout = FRAME_TIME.saturating_sub(last_draw.elapsed());
+        if let Some(event) = term.poll(timeout)? {
+            if !lock()?.event(event)? {
+                return Ok(());
+            }
         }
     }
+    Ok(())
 }
 
-struct App {
-    particle_groups: [ParticleGroup; COLORS.len()],
-    gravity: Vector,
-    rng: ThreadRng,
+// a frame drawn off screen, copied over as it is
+struct Drawn(Buffer);
+
+impl Widget for Drawn {
+    fn render(self, _: Rect, buf: &mut Buffer) {
+        buf.merge(&self.0);
+    }
 }
 
-impl App {
-    fn new() -> App {
-        let mut tmp: [ParticleGroup; COLORS.len()] =
-            [ParticleGroup::new(Color::Black); COLORS.len()];
-        for (i, c) in COLORS.iter().enumerate() {
-            tmp[i].color = c.clone();
+// what the main loop keeps between frames, apart from the clock
+struct Session {
+    app: App,
+    screen: Screen,
+    // simulation steps taken so far, the clock recordings are kept against
+    ticks: u64,
+    // everything holds while the window is shrunk below the minimum
+    small: bool,
+    started: Instant,
+}
+
+impl Session {
+    fn new(mut app: App, size: Rect) -> Result<Session, Error> {
+        let size = replayed_size(&app, size);
+        if too_small(size) {
+            return Err(Error::TooSmall {
+                width: size.width,
+                height: size.height,
+            });
         }
-        App {
-            particle_groups: tmp,
-            gravity: Vector { x: 0.0, y: -0.004 },
-            rng: rand::thread_rng(),
+        let screen = Screen::new(size, app.reflection);
+        app.fit_edges(&screen);
+        app.fit_message(&screen);
+        app.fit_budget(size);
+        Ok(Session {
+            app,
+            screen,
+            ticks: 0,
+            small: false,
+            started: Instant::now(),
+        })
+    }
+
+    fn summary(&self) -> Summary {
+        Summary {
+            ran: self.started.elapsed(),
+            frames: self.app.frames,
+            sim: self.app.sim.stats(),
         }
     }
 
-    fn on_tick(&mut self) {
-        for particle_group in self.particle_groups.iter_mut() {
-            for i in 0..MAX_PARTICLES_COLOR {
-                particle_group.particles[i].apply_force(self.gravity);
-                particle_group.particles[i].update();
-                particle_group.pos[i] = (
-                    particle_group.particles[i].pos.x,
-                    particle_group.particles[i].pos.y,
-                );
+    // follows the terminal to its current size and draws a frame
+    fn draw<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<(), Error>
+    where
+        Error: From<B::Error>,
+    {
+        let _draw = debug_span!("draw").entered();
+        let size = Rect::from(terminal.size()?);
+        self.small = too_small(size);
+        // a replay goes by the sizes it was recorded at instead
+        if !self.small && size != self.screen.size && self.app.replay.is_none() {
+            resize(&mut self.app, &mut self.screen, size);
+            if let Some(recorder) = self.app.recorder.as_mut() {
+                let (width, height) = (size.width, size.height);
+                recorder.log(self.ticks, Input::Resize { width, height })?;
+            }
+        }
+        if self.small {
+            terminal.draw(too_small_ui)?;
+        } else {
+            let frame = terminal.draw(|f| ui(f, &mut self.app))?;
+            if self.app.screenshot {
+                self.app.screenshot = false;
+                // failing to save it is no reason to stop the show
+                let text = match screenshot::save(&self.app, frame.buffer, Path::new(".")) {
+                    Ok(name) => {
+                        let name = name.file_name().unwrap_or_default().to_string_lossy();
+                        format!("saved {}.ans and .png", name)
+                    }
+                    Err(err) => format!("no screenshot: {}", err),
+                };
+                self.app.notify(text);
+            }
+        }
+        Ok(())
+    }
+
+    // takes a keyboard or mouse event, telling whether to keep going
+    fn event(&mut self, event: Event) -> io::Result<bool> {
+        match read_input(event, &self.app.keymap) {
+            // a replay only takes quit from the keyboard
+            Some(input) if self.app.replay.is_none() || input == Input::Action(Action::Quit) => {
+                handle(&mut self.app, input, self.ticks, &self.screen)
+            }
+            _ => Ok(true),
+        }
+    }
 
-                if !particle_group.particles[i].dont_delete {
-                    particle_group.pos[i] = (9999.9, 9999.9);
-                    if !particle_group.particles[i].exploded
-                        && !particle_group.particles[i].subparticle
-                    {
-                        for _ in 1..20 {
-                            create_particle(
-                                particle_group,
-                                true,
-                                particle_group.particles[i].pos,
-                                random_unit_vector(&mut self.rng) * self.rng.gen_range(0.2..0.4),
-                            );
-                        }
-                        particle_group.particles[i].exploded = true;
-                        continue;
+    // one simulation step `dt` seconds long before the time scale, then
+    // whatever is lined up for the next one
+    fn step(&mut self, dt: f64) -> io::Result<bool> {
+        let _step = debug_span!("step", tick = self.ticks + 1).entered();
+        // what came in before the first step, as it did while recording
+        if self.ticks == 0 && !self.scheduled()? {
+            return Ok(false);
+        }
+        // stretching each step rather than taking more or fewer of them
+        // keeps slow motion smooth and the ticks a replay goes by intact
+        let started = Instant::now();
+        self.app.sim.step(dt * self.app.time_scale);
+        self.ticks += 1;
+        debug!(
+            target: "tick",
+            "took {:.3}ms, {} particles {:?}",
+            started.elapsed().as_secs_f64() * 1000.0,
+            self.app.sim.particle_count(),
+            self.app.sim.particle_counts().map(|(_, n)| n).collect::<Vec<_>>()
+        );
+        for event in self.app.sim.take_events() {
+            #[cfg(feature = "sound")]
+            if let Some(sound) = self.app.sound.as_ref() {
+                sound.play(event);
+            }
+            if let (
+                Some(host),
+                sim::Event::Launch {
+                    color_index,
+                    pos,
+                    vel,
+                    burst,
+                },
+            ) = (self.app.host.as_ref(), event)
+            {
+                host.send(self.screen.to_rocket(color_index, pos, vel, burst));
+            }
+        }
+        self.scheduled()
+    }
+
+    // whatever the replay, the auto show or the show file has lined up for
+    // this step, telling whether to keep going
+    fn scheduled(&mut self) -> io::Result<bool> {
+        let (app, ticks, screen) = (&mut self.app, self.ticks, &mut self.screen);
+        let mut inputs = Vec::new();
+        if let Some(replay) = app.replay.as_mut() {
+            inputs.extend(replay.due(ticks));
+        }
+        if let Some(auto) = app.auto.as_mut() {
+            if !app.paused && !self.small {
+                inputs.extend(auto.due(ticks, &mut app.rng));
+            }
+        }
+        if let Some(audio) = app.audio.as_ref() {
+            if !app.paused && !self.small {
+                inputs.extend(
+                    audio
+                        .beats()
+                        .into_iter()
+                        .map(|strength| Input::Beat { strength }),
+                );
+            }
+        }
+        // like the keyboard, other programs get through while paused
+        if let Some(control) = app.control.as_ref() {
+            inputs.extend(control.inputs());
+        }
+        if let Some(client) = app.client.as_ref() {
+            for news in client.news() {
+                match news {
+                    // our own --message wins
+                    News::Message(text) if app.message.is_none() => {
+                        app.message = Some(text);
+                        app.fit_message(screen);
+                    }
+                    News::Message(_) => {}
+                    News::Rocket(rocket) => {
+                        let (pos, vel) = screen.place_rocket(rocket);
+                        // with fewer colors than the host, some double up
+                        let color = rocket.color % app.sim.palette().len();
+                        app.sim.launch_burst(color, pos, vel, rocket.burst);
+                    }
+                }
+            }
+        }
+        // goes by the wall clock, so it goes off even while paused
+        let now = SystemTime::now();
+        if let Some(countdown) = app.countdown.as_mut() {
+            inputs.extend(countdown.due(now));
+        }
+        for trigger in app.triggers.iter_mut() {
+            inputs.extend(trigger.due(now));
+        }
+        // what the demo and the show file do is logged like the rest, as a
+        // replay goes without them
+        if let Some(demo) = app.demo.as_mut() {
+            if !self.small {
+                for cue in demo.due(ticks) {
+                    match cue {
+                        Cue::Caption(text) => app.caption = Some(text),
+                        Cue::Theme(name) => inputs.push(Input::Theme(name)),
+                        Cue::Launch(launch) => inputs.push(Input::Launch(launch)),
+                        Cue::Finale => inputs.push(Input::Action(Action::Finale)),
                     }
+                }
+            }
+        }
+        if let Some(show) = app.show.as_mut() {
+            inputs.extend(show.due(ticks).into_iter().map(Input::Launch));
+        }
+        for input in inputs {
+            if let Input::Resize { width, height } = input {
+                // a hand-edited log may ask for less than the show can fit
+                let size = Rect::new(0, 0, width, height);
+                if too_small(size) {
                     continue;
                 }
+                resize(app, screen, size);
+            }
+            if !handle(app, input, ticks, screen)? {
+                return Ok(false);
             }
         }
+
+        for launch in app.schedule.due(ticks) {
+            app.launch_planned(launch, screen);
+        }
+        if let Some(show) = app.show.as_mut() {
+            // wait for the last rocket to burn out before ending or looping
+            if show.is_over() && app.sim.is_empty() && !show.rewind(ticks) {
+                // which a replay has nothing else to end on
+                if let Some(recorder) = app.recorder.as_mut() {
+                    recorder.log(ticks, Input::Action(Action::Quit))?;
+                }
+                return Ok(false);
+            }
+        }
+        Ok(true)
     }
 }
 
-fn create_particle(pgroup: &mut ParticleGroup, is_sub: bool, pos: Vector, vel: Vector) {
-    let p = Particle::new(is_sub, pos, vel);
-    pgroup.particles[pgroup.add_at] = p;
-    pgroup.pos[pgroup.add_at] = (pos.x, pos.y);
-    pgroup.add_at += 1;
-    if pgroup.add_at == MAX_PARTICLES_COLOR {
-        pgroup.add_at = 0;
+// the size a replay was recorded at, which it goes by whatever the size of
+// the terminal it's shown on
+fn replayed_size(app: &App, size: Rect) -> Rect {
+    match app.replay.as_ref().and_then(|r| r.size) {
+        Some((width, height)) => Rect::new(0, 0, width, height),
+        None => size,
     }
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
-    // setup terminal
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+// moves the session over to a screen of `size` cells
+fn resize(app: &mut App, screen: &mut Screen, size: Rect) {
+    info!("resized to {}x{}", size.width, size.height);
+    let resized = Screen::new(size, app.reflection);
+    app.resize(screen, &resized);
+    *screen = resized;
+}
 
-    // create app and run it
-    let tick_rate = Duration::from_millis(10);
-    let app = App::new();
-    let res = run_app(&mut terminal, app, tick_rate);
+// most steps taken in one go when the simulation falls behind
+const MAX_CATCH_UP: u32 = 10;
+// time between two frames, however often the simulation steps
+const FRAME_TIME: Duration = Duration::from_millis(16);
+// waves in a finale, which takes about ten seconds
+const FINALE_WAVES: u32 = 18;
+// slowest and fastest the simulation can be made to run
+const TIME_SCALES: [f64; 2] = [0.125, 4.0];
+// how far a key press moves the wind and the turbulence, and how far they go
+const WIND_STEP: f64 = 5.0;
+const MAX_WIND: f64 = 50.0;
+const TURBULENCE_STEP: f64 = 5.0;
+const MAX_TURBULENCE: f64 = 50.0;
+// and the same for gravity, the thickness of the air and embers per burst
+const GRAVITY_STEP: f64 = 5.0;
+const MAX_GRAVITY: f64 = 120.0;
+const DRAG_STEP: f64 = 0.25;
+const MAX_DRAG: f64 = 4.0;
+const SPARKS_STEP: usize = 5;
+const MAX_SPARKS: usize = 200;
+// how far a key press moves the launcher, as a share of the screen width,
+// tilts it and changes its power
+const AIM_STEP: f64 = 0.02;
+const TILT_STEP: f64 = 5.0;
+const POWER_STEP: f64 = 0.05;
+// how fast and how much of the usual show goes on with --reduced-motion,
+// and the brightest a color may be and still be drawn with --high-contrast
+const REDUCED_SPEED: f64 = 0.5;
+const REDUCED_MOTION: f64 = 0.5;
+const CONTRAST_FLOOR: u8 = 96;
+// where --debug logs to without --log
+const DEBUG_LOG: &str = "firewors.log";
+// how long a changed setting stays on screen, and the summary after quitting
+const NOTICE_TIME: Duration = Duration::from_millis(1500);
+const SUMMARY_TIME: Duration = Duration::from_secs(10);
 
-    // restore terminal
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
+// a rocket bursting into the message a little above the middle of the
+// screen, where there's room for the text to fall
+fn message_launch() -> Launch {
+    Launch {
+        x: Some(0.0),
+        color: None,
+        burst: Some(BurstType::Message),
+        speed: Some(0.9),
+    }
+}
 
-    if let Err(err) = res {
-        println!("{:?}", err)
+// logs and applies an input, telling whether to keep going
+fn handle(app: &mut App, input: Input, ticks: u64, screen: &Screen) -> io::Result<bool> {
+    if let Some(recorder) = app.recorder.as_mut() {
+        recorder.log(ticks, input)?;
     }
+    if input == Input::Action(Action::Quit) {
+        app.quit = true;
+        return Ok(false);
+    }
+    app.apply(input, screen);
+    Ok(true)
+}
 
-    Ok(())
+fn read_input(event: Event, keymap: &Keymap) -> Option<Input> {
+    match event {
+        Event::Key(key) => keymap.resolve(key.into()).map(Input::Action),
+        Event::Mouse(mouse) => match mouse.kind {
+            MouseEventKind::Down(MouseButton::Right) | MouseEventKind::Drag(MouseButton::Right) => {
+                Some(Input::Attract {
+                    column: mouse.column,
+                    row: mouse.row,
+                })
+            }
+            MouseEventKind::Up(MouseButton::Right) => Some(Input::Release),
+            MouseEventKind::Down(MouseButton::Left) => Some(Input::LaunchAt {
+                column: mouse.column,
+                row: mouse.row,
+            }),
+            _ => None,
+        },
+        _ => None,
+    }
 }
 
-fn run_app<B: Backend>(
-    terminal: &mut Terminal<B>,
-    mut app: App,
-    tick_rate: Duration,
-) -> io::Result<()> {
-    let mut rng = rand::thread_rng();
-    let w_int = terminal.get_frame().size().width;
-    let h_int = terminal.get_frame().size().height;
-    let w_float = f64::from(w_int);
-    let h_float = f64::from(h_int);
+const MIN_WIDTH: u16 = 10;
+const MIN_HEIGHT: u16 = 10;
 
-    let max_speed: f64 = 0.08 * h_float.powf(0.5);
+fn too_small(size: Rect) -> bool {
+    size.width < MIN_WIDTH || size.height < MIN_HEIGHT
+}
 
-    let mut last_tick = Instant::now();
-    loop {
-        terminal.draw(|f| ui(f, &mut app))?;
-
-        let timeout = tick_rate
-            .checked_sub(last_tick.elapsed())
-            .unwrap_or_else(|| Duration::from_secs(0));
-        if event::poll(timeout)? {
-            if let Event::Key(key) = event::read()? {
-                match key.code {
-                    KeyCode::Char('q') => {
-                        return Ok(());
-                    }
-                    KeyCode::Char('m') => {
-                        for i in -10..10 {
-                            let pos_x = f64::from(i) * w_float / 20.0;
-                            let speed_y = rng.gen_range(max_speed * 0.8..max_speed);
-                            let speed_x = rng.gen_range(-0.08..0.08);
-                            create_particle(
-                                &mut app.particle_groups[rng.gen_range(0..COLORS.len())],
-                                false,
-                                Vector {
-                                    x: pos_x,
-                                    y: -h_float / 2.0,
-                                },
-                                Vector {
-                                    x: speed_x,
-                                    y: speed_y,
-                                },
-                            );
-                        }
-                    }
-                    KeyCode::Char('f') => {
-                        let pos_x = rng.gen_range(-w_float / 2.0..w_float / 2.0);
-                        let speed_y = rng.gen_range(max_speed * 0.8..max_speed);
-                        let speed_x = rng.gen_range(-0.08..0.08);
-                        create_particle(
-                            &mut app.particle_groups[rng.gen_range(0..COLORS.len())],
-                            false,
-                            Vector {
-                                x: pos_x,
-                                y: -h_float / 2.0,
-                            },
-                            Vector {
-                                x: speed_x,
-                                y: speed_y,
-                            },
-                        );
-                    }
-                    _ => {}
-                }
-            }
-        }
+// the part of the screen above the water, which never takes more than half
+fn sky(size: Rect, reflection: u16) -> Rect {
+    Rect {
+        height: size.height - reflection.min(size.height / 2),
+        ..size
+    }
+}
 
-        if last_tick.elapsed() >= tick_rate {
-            app.on_tick();
-            last_tick = Instant::now();
-        }
+// how far each row of water is pushed sideways, and how fast that moves
+const RIPPLE: f64 = 0.6;
+const RIPPLE_SPEED: f64 = 0.15;
+// what's left of the colors in the water
+const REFLECTED: f64 = 0.45;
+
+// the sky squashed upside down into `water` rows, dimmer and rippling
+fn reflected(app: &App, sky_height: f64, water: f64) -> Vec<(f64, f64, Color, Glyph)> {
+    let phase = app.frames as f64 * RIPPLE_SPEED;
+    let glyphs = glyphs(app);
+    app.sim
+        .particles_by_kind()
+        .filter_map(|(x, y, color, kind)| {
+            // the bottom of the sky meets the top of the water
+            let y = water / 2.0 - (y + sky_height / 2.0) * water / sky_height;
+            let x = x + RIPPLE * (y * 3.0 + phase).sin();
+            let (r, g, b) = ansi::to_rgb(color);
+            let dim = |c: u8| (f64::from(c) * REFLECTED) as u8;
+            let color = shown_color(app, Color::Rgb(dim(r), dim(g), dim(b)))?;
+            Some((x, y, color, glyphs.of(kind)))
+        })
+        .collect()
+}
+
+// particles at canvas coordinates centered on `area`
+fn draw_particles(
+    f: &mut Frame,
+    area: Rect,
+    particles: impl Iterator<Item = (f64, f64, Color, Glyph)>,
+) {
+    let x_bounds = [-f64::from(area.width) / 2.0, f64::from(area.width) / 2.0];
+    let y_bounds = [-f64::from(area.height) / 2.0, f64::from(area.height) / 2.0];
+    let lines = paint::rasterize(particles, area.width, area.height, x_bounds, y_bounds);
+    f.render_widget(paint::Overlay(lines), area);
+}
+
+fn too_small_ui(f: &mut Frame) {
+    let text = Paragraph::new("terminal too small").wrap(Wrap { trim: true });
+    f.render_widget(text, f.area());
+}
+
+// `color` as the terminal can show it
+fn drawn_color(app: &App, color: Color) -> Color {
+    match app.depth {
+        Depth::Truecolor => color,
+        Depth::Indexed => ansi::to_indexed(color),
+        Depth::Ansi => ansi::to_ansi(color),
+    }
+}
+
+// the color a particle of `color` is drawn in, if it is drawn at all
+fn shown_color(app: &App, color: Color) -> Option<Color> {
+    if !app.high_contrast {
+        return Some(drawn_color(app, color));
+    }
+    // as bright as its hue goes, unless it has faded too far to tell
+    let (r, g, b) = ansi::to_rgb(color);
+    let top = r.max(g).max(b);
+    if top < CONTRAST_FLOOR {
+        return None;
+    }
+    let boost = |c: u8| (u16::from(c) * 255 / u16::from(top)) as u8;
+    Some(drawn_color(app, Color::Rgb(boost(r), boost(g), boost(b))))
+}
+
+fn drawn_particles(app: &App) -> impl Iterator<Item = (f64, f64, Color, Kind)> + '_ {
+    app.sim
+        .particles_by_kind()
+        .filter_map(move |(x, y, color, kind)| Some((x, y, shown_color(app, color)?, kind)))
+}
+
+// what particles are drawn with: the glyphs picked for each kind, or one
+// plainer glyph for all of them after `b`
+fn glyphs(app: &App) -> Glyphs {
+    match app.braille {
+        true => app.glyphs,
+        false => app.plain,
     }
 }
 
-fn ui<B: Backend>(f: &mut Frame<B>, app: &mut App) {
-    let chunks = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(100)].as_ref())
-        .split(f.size());
-    let canvas = Canvas::default()
-        .block(Block::default())
-        .paint(|ctx| {
-            for particle_group in app.particle_groups {
-                ctx.draw(&Points {
-                    color: particle_group.color,
-                    coords: &particle_group.pos,
-                });
+// the keys listed in the status overlay, two to a line
+const CHEAT_SHEET: &[(Action, &str)] = &[
+    (Action::Launch, "launch"),
+    (Action::MultiLaunch, "salvo"),
+    (Action::Finale, "finale"),
+    (Action::LaunchMessage, "message"),
+    (Action::CycleShape, "shape"),
+    (Action::CycleDepth, "stages"),
+    (Action::CycleFountains, "fountains"),
+    (Action::CycleGround, "ground"),
+    (Action::ToggleTrails, "trails"),
+    (Action::ToggleSmoke, "smoke"),
+    (Action::Pause, "pause"),
+    (Action::ToggleBraille, "braille"),
+    (Action::SlowDown, "slower"),
+    (Action::SpeedUp, "faster"),
+    (Action::WindLeft, "wind left"),
+    (Action::WindRight, "wind right"),
+    (Action::MoreTurbulence, "gustier"),
+    (Action::LessTurbulence, "calmer"),
+    (Action::MoreGravity, "heavier"),
+    (Action::LessGravity, "lighter"),
+    (Action::MoreDrag, "more drag"),
+    (Action::LessDrag, "less drag"),
+    (Action::MoreSparks, "denser"),
+    (Action::FewerSparks, "sparser"),
+    (Action::ToggleAim, "aim"),
+    (Action::Fire, "fire"),
+    (Action::TiltLeft, "tilt left"),
+    (Action::TiltRight, "tilt right"),
+    #[cfg(feature = "sound")]
+    (Action::ToggleSound, "sound"),
+    (Action::Screenshot, "screenshot"),
+    (Action::ToggleHud, "status"),
+    (Action::Quit, "quit"),
+];
+
+#[cfg(feature = "sound")]
+fn muted(app: &App) -> bool {
+    app.sound.as_ref().is_some_and(Sound::is_muted)
+}
+
+#[cfg(not(feature = "sound"))]
+fn muted(_: &App) -> bool {
+    false
+}
+
+// muting means nothing without --sound
+#[cfg(feature = "sound")]
+fn pointless(app: &App, action: Action) -> bool {
+    action == Action::ToggleSound && app.sound.is_none()
+}
+
+#[cfg(not(feature = "sound"))]
+fn pointless(_: &App, _: Action) -> bool {
+    false
+}
+
+fn status_lines(app: &App) -> Vec<Line<'static>> {
+    let mut lines = vec![
+        Line::from(format!(
+            "fps {:.0}  speed {}x{}{}",
+            app.fps,
+            app.time_scale,
+            if app.paused { "  paused" } else { "" },
+            if muted(app) { "  muted" } else { "" }
+        )),
+        Line::from(format!(
+            "gravity {:.1}  wind {:.1}  turbulence {:.0}",
+            -app.sim.gravity().y,
+            app.sim.wind_now(),
+            app.sim.turbulence()
+        )),
+        Line::from(format!(
+            "particles {}{}",
+            app.sim.particle_count(),
+            match app.budget.load() {
+                load if load < 1.0 => format!("  load {:.0}%", load * 100.0),
+                _ => String::new(),
             }
+        )),
+    ];
+    let counts: Vec<(Color, usize)> = app.sim.particle_counts().collect();
+    for row in counts.chunks(3) {
+        let mut spans = Vec::new();
+        for (color, count) in row {
+            let swatch = Style::default().fg(drawn_color(app, *color));
+            spans.push(Span::styled("■", swatch));
+            spans.push(Span::raw(format!(" {:<6}", count)));
+        }
+        lines.push(Line::from(spans));
+    }
+    lines.push(Line::default());
+
+    let mut keys: Vec<String> = CHEAT_SHEET
+        .iter()
+        .filter(|(action, _)| !pointless(app, *action))
+        .filter_map(|(action, label)| {
+            let key = app.keymap.key(*action)?;
+            Some(format!("{:<5} {:<11}", key_name(key), label))
         })
-        .x_bounds([
-            -f64::from(f.size().width) / 2.0,
-            f64::from(f.size().width) / 2.0,
-        ])
-        .y_bounds([
-            -f64::from(f.size().height) / 2.0,
-            f64::from(f.size().height) / 2.0,
-        ]);
-    f.render_widget(canvas, chunks[0]);
+        .collect();
+    let colors = counts.len().min(9);
+    if app.keymap.key(Action::LaunchColor(0)).is_some() {
+        keys.push(format!(
+            "{:<5} {:<11}",
+            format!("1-{}", colors),
+            "one color"
+        ));
+    }
+    for pair in keys.chunks(2) {
+        lines.push(Line::from(pair.concat().trim_end().to_string()));
+    }
+    lines
+}
+
+// a box in the top left corner with the state of things and the keys
+fn status_overlay(f: &mut Frame, app: &App) {
+    let lines = status_lines(app);
+    let width = lines.iter().map(|l| l.width()).max().unwrap_or(0) as u16 + 4;
+    let height = lines.len() as u16 + 2;
+    let area = Rect::new(0, 0, width.min(f.area().width), height.min(f.area().height));
+    let block = Block::default().borders(Borders::ALL).title(" status ");
+    f.render_widget(Clear, area);
+    f.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+// a line in a box at the top of the screen, in the middle
+fn notice(f: &mut Frame, text: &str) {
+    let size = f.area();
+    let width = (text.chars().count() as u16 + 4).min(size.width);
+    let area = Rect::new((size.width - width) / 2, 0, width, 3.min(size.height));
+    f.render_widget(Clear, area);
+    let block = Block::default().borders(Borders::ALL);
+    let text = Paragraph::new(text.to_string()).alignment(Alignment::Center);
+    f.render_widget(text.block(block), area);
+}
+
+// the summary in a box in the middle of an otherwise empty screen
+fn summary_ui(f: &mut Frame, lines: &[String]) {
+    let size = f.area();
+    let longest = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+    let width = (longest as u16 + 4).min(size.width);
+    let height = (lines.len() as u16 + 2).min(size.height);
+    let area = Rect::new(
+        (size.width - width) / 2,
+        (size.height - height) / 2,
+        width,
+        height,
+    );
+    let block = Block::default().borders(Borders::ALL).title(" show over ");
+    let text: Vec<Line> = lines
+        .iter()
+        .map(|l| Line::from(format!(" {}", l)))
+        .collect();
+    f.render_widget(Paragraph::new(text).block(block), area);
+}
+
+// a line of text along the bottom of the screen, in the middle
+fn caption(f: &mut Frame, text: &str, style: Style) {
+    let size = f.area();
+    let area = Rect::new(0, size.height.saturating_sub(2), size.width, 1);
+    let text = Paragraph::new(Span::styled(text.to_string(), style));
+    f.render_widget(text.alignment(Alignment::Center), area);
+}
+
+fn ui(f: &mut Frame, app: &mut App) {
+    let area = f.area();
+    let sky = sky(area, app.reflection);
+    app.frames += 1;
+    if !app.scenery.is_empty() {
+        app.scenery.next_frame(sky);
+        let backdrop = Backdrop {
+            scenery: &app.scenery,
+            color: |color| drawn_color(app, color),
+        };
+        f.render_widget(backdrop, sky);
+    }
+    // smoke behind the sparks, which cover it where they meet
+    let smoke = app
+        .sim
+        .smoke()
+        .filter_map(|(x, y, color)| Some((x, y, shown_color(app, color)?)));
+    let glyphs = glyphs(app);
+    draw_particles(f, sky, smoke.map(|(x, y, c)| (x, y, c, glyphs.smoke)));
+    let sparks = drawn_particles(app).map(|(x, y, c, kind)| (x, y, c, glyphs.of(kind)));
+    draw_particles(f, sky, sparks);
+    if sky.height < area.height {
+        let water = Rect {
+            y: area.y + sky.height,
+            height: area.height - sky.height,
+            ..area
+        };
+        let particles = reflected(app, f64::from(sky.height), f64::from(water.height));
+        draw_particles(f, water, particles.into_iter());
+    }
+    if let Some(left) = app
+        .countdown
+        .as_ref()
+        .and_then(|c| c.remaining(SystemTime::now()))
+    {
+        let text = clock(left);
+        let style = Style::default().fg(drawn_color(app, Color::Rgb(255, 255, 255)));
+        f.render_widget(BigClock { text: &text, style }, area);
+    }
+    if let Some(launcher) = app.aim {
+        let screen = Screen::new(area, app.reflection);
+        let pos = Vector {
+            x: launcher.x * screen.width,
+            y: -screen.height / 2.0,
+        };
+        let vel = launcher.velocity(screen.max_speed * app.launch_speed);
+        let gravity = app.sim.gravity()
+            + Vector {
+                x: app.sim.wind_now(),
+                y: 0.0,
+            };
+        let path = aim::path(pos, vel, gravity);
+        let sight = Sight {
+            launcher: pos,
+            path: &path,
+            color: |color| drawn_color(app, color),
+        };
+        f.render_widget(sight, sky);
+    }
+    if let Some(text) = &app.caption {
+        let white = drawn_color(app, Color::Rgb(255, 255, 255));
+        caption(
+            f,
+            text,
+            Style::default().fg(white).add_modifier(Modifier::BOLD),
+        );
+    }
+    if let Some((text, until)) = &app.notice {
+        if Instant::now() < *until {
+            notice(f, text);
+        }
+    }
+    if app.hud {
+        status_overlay(f, app);
+    }
 }
@@ -0,0 +1,165 @@
+use firewors::sim::{BurstType, Vector};
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+// The host sends every rocket that goes up as one line, its position as a
+// share of the screen size and its velocity as a share of the top launch
+// speed, so it comes out the same in a terminal of any size:
+//
+//     message HAPPY 2025
+//     rocket -0.25 -0.5 0.02 0.93 3 heart
+//
+// with the message (if there is one) sent once on connecting.
+
+/// A rocket that went up on the host, sized to no screen in particular.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Rocket {
+    pub color: usize,
+    pub pos: Vector,
+    pub vel: Vector,
+    pub burst: BurstType,
+}
+
+impl Rocket {
+    fn to_line(self) -> String {
+        format!(
+            "rocket {} {} {} {} {} {}",
+            self.pos.x,
+            self.pos.y,
+            self.vel.x,
+            self.vel.y,
+            self.color + 1,
+            self.burst.name()
+        )
+    }
+
+    fn parse(words: &[&str]) -> Option<Rocket> {
+        let [x, y, vx, vy, color, burst] = words else {
+            return None;
+        };
+        let number = |word: &str| word.parse::<f64>().ok().filter(|x| x.is_finite());
+        Some(Rocket {
+            color: color.parse::<usize>().ok()?.checked_sub(1)?,
+            pos: Vector {
+                x: number(x)?,
+                y: number(y)?,
+            },
+            vel: Vector {
+                x: number(vx)?,
+                y: number(vy)?,
+            },
+            burst: BurstType::from_name(burst)?,
+        })
+    }
+}
+
+/// What a client hears from the host.
+pub enum News {
+    Message(String),
+    Rocket(Rocket),
+}
+
+/// Sends every rocket to whoever connects, each client on a thread of its
+/// own so a slow one holds nobody up.
+pub struct Host {
+    clients: Arc<Mutex<Vec<Sender<String>>>>,
+}
+
+impl Host {
+    pub fn listen(addr: &str, message: Option<String>) -> io::Result<Host> {
+        let listener = TcpListener::bind(addr)?;
+        let clients = Arc::new(Mutex::new(Vec::new()));
+        let accepted = Arc::clone(&clients);
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let (tx, rx) = mpsc::channel();
+                if let Some(text) = &message {
+                    let _ = tx.send(format!("message {}", text));
+                }
+                accepted.lock().unwrap().push(tx);
+                thread::spawn(move || serve(stream, rx));
+            }
+        });
+        Ok(Host { clients })
+    }
+
+    pub fn send(&self, rocket: Rocket) {
+        let line = rocket.to_line();
+        // clients that went away are dropped on the next rocket
+        let mut clients = self.clients.lock().unwrap();
+        clients.retain(|client| client.send(line.clone()).is_ok());
+    }
+}
+
+fn serve(mut stream: TcpStream, lines: Receiver<String>) {
+    let _ = stream.set_nodelay(true);
+    for line in lines {
+        if writeln!(stream, "{}", line).is_err() {
+            return;
+        }
+    }
+}
+
+/// Rockets coming in from a host, read on a thread of their own.
+pub struct Client {
+    news: Receiver<News>,
+}
+
+impl Client {
+    pub fn connect(addr: &str) -> io::Result<Client> {
+        let stream = TcpStream::connect(addr)?;
+        let (tx, news) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stream).lines() {
+                let Ok(line) = line else { return };
+                let news = match line.split_once(' ') {
+                    Some(("message", text)) => Some(News::Message(text.to_string())),
+                    Some(("rocket", rest)) => {
+                        let words: Vec<&str> = rest.split_whitespace().collect();
+                        Rocket::parse(&words).map(News::Rocket)
+                    }
+                    // from a newer host perhaps, nothing to worry about
+                    _ => None,
+                };
+                if let Some(news) = news {
+                    if tx.send(news).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        Ok(Client { news })
+    }
+
+    /// What came in since the last call.
+    pub fn news(&self) -> Vec<News> {
+        self.news.try_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rockets_survive_the_trip() {
+        let rocket = Rocket {
+            color: 2,
+            pos: Vector { x: -0.25, y: -0.5 },
+            vel: Vector { x: 0.03, y: 0.9 },
+            burst: BurstType::Willow,
+        };
+        let line = rocket.to_line();
+        let words: Vec<&str> = line.split_whitespace().collect();
+        assert_eq!(words[0], "rocket");
+        assert_eq!(Rocket::parse(&words[1..]), Some(rocket));
+        assert_eq!(Rocket::parse(&["0", "0", "0", "1", "0", "ring"]), None);
+    }
+}
@@ -0,0 +1,60 @@
+/// How hard the auto show goes over a round, from 0 for the odd rocket
+/// bursting low and small to 1 for salvos as big and quick as they come:
+/// a curve through evenly spaced points, straight between them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Envelope {
+    points: Vec<f64>,
+}
+
+impl Default for Envelope {
+    // building up steadily from nothing
+    fn default() -> Self {
+        Envelope {
+            points: vec![0.0, 1.0],
+        }
+    }
+}
+
+impl Envelope {
+    pub fn new(points: Vec<f64>) -> Result<Envelope, String> {
+        if points.len() < 2 {
+            return Err("the envelope needs at least two points".into());
+        }
+        if let Some(point) = points.iter().find(|p| !(0.0..=1.0).contains(*p)) {
+            return Err(format!("`{}` is not an intensity from 0 to 1", point));
+        }
+        Ok(Envelope { points })
+    }
+
+    /// The intensity `progress` of the way through a round, from 0 to 1.
+    pub fn at(&self, progress: f64) -> f64 {
+        let last = self.points.len() - 1;
+        let x = progress.clamp(0.0, 1.0) * last as f64;
+        let i = (x as usize).min(last - 1);
+        let (from, to) = (self.points[i], self.points[i + 1]);
+        from + (to - from) * (x - i as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn straight_between_the_points() {
+        let envelope = Envelope::new(vec![0.2, 0.2, 1.0]).unwrap();
+        assert_eq!(envelope.at(0.0), 0.2);
+        assert_eq!(envelope.at(0.25), 0.2);
+        assert!((envelope.at(0.75) - 0.6).abs() < 1e-9);
+        assert_eq!(envelope.at(1.0), 1.0);
+        assert_eq!(envelope.at(2.0), 1.0);
+        assert_eq!(Envelope::default().at(0.3), 0.3);
+    }
+
+    #[test]
+    fn rejects_what_isnt_a_curve() {
+        assert!(Envelope::new(vec![0.5]).is_err());
+        assert!(Envelope::new(vec![0.0, 1.5]).is_err());
+        assert!(Envelope::new(vec![f64::NAN, 1.0]).is_err());
+    }
+}
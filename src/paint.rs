@@ -0,0 +1,222 @@
+use firewors::sim::Kind;
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::Widget,
+};
+
+// bit of the braille pattern for the dot at (column, row) within a cell
+const DOTS: [[u8; 4]; 2] = [[0x01, 0x02, 0x04, 0x40], [0x08, 0x10, 0x20, 0x80]];
+// half blocks by the halves of the cell they fill, the top one in bit 0
+const HALVES: [char; 4] = [' ', '▀', '▄', '█'];
+
+/// How a kind of particle is drawn.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Glyph {
+    /// Braille dots, 2x4 to a cell.
+    Braille,
+    /// Half blocks, one above the other.
+    Block,
+    /// The same character wherever there is anything in the cell.
+    Char(char),
+}
+
+impl Glyph {
+    /// `braille`, `block`, `dot` or any one character.
+    pub fn parse(name: &str) -> Option<Glyph> {
+        match name {
+            "braille" => Some(Glyph::Braille),
+            "block" => Some(Glyph::Block),
+            "dot" => Some(Glyph::Char('•')),
+            _ => {
+                let mut chars = name.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) if !c.is_whitespace() && !c.is_control() => {
+                        Some(Glyph::Char(c))
+                    }
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    // coarser glyphs take a cell over from finer ones, which would be lost
+    // under them anyway
+    fn rank(self) -> u8 {
+        match self {
+            Glyph::Braille => 0,
+            Glyph::Block => 1,
+            Glyph::Char(_) => 2,
+        }
+    }
+}
+
+/// The glyph each kind of particle is drawn with, and the smoke.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Glyphs {
+    pub rocket: Glyph,
+    pub spark: Glyph,
+    pub trail: Glyph,
+    pub smoke: Glyph,
+}
+
+impl Glyphs {
+    /// Braille for everything, as fine as a terminal draws.
+    pub const BRAILLE: Glyphs = Glyphs::all(Glyph::Braille);
+    /// Nothing outside ASCII, for terminals and fonts without the rest.
+    pub const ASCII: Glyphs = Glyphs {
+        rocket: Glyph::Char('^'),
+        spark: Glyph::Char('*'),
+        trail: Glyph::Char('.'),
+        smoke: Glyph::Char(':'),
+    };
+
+    pub const fn all(glyph: Glyph) -> Glyphs {
+        Glyphs {
+            rocket: glyph,
+            spark: glyph,
+            trail: glyph,
+            smoke: glyph,
+        }
+    }
+
+    pub fn of(&self, kind: Kind) -> Glyph {
+        match kind {
+            Kind::Rocket => self.rocket,
+            Kind::Spark => self.spark,
+            Kind::Trail => self.trail,
+        }
+    }
+}
+
+#[derive(Default, Clone)]
+struct Cell {
+    // what it is drawn with, and which of its dots or halves are lit
+    glyph: Option<Glyph>,
+    bits: u8,
+    votes: Vec<(Color, u32)>,
+}
+
+impl Cell {
+    // lights the dot at (column, row) of the 2x4 the cell is cut into
+    fn light(&mut self, glyph: Glyph, column: usize, row: usize, color: Color) {
+        match self.glyph {
+            Some(own) if own.rank() > glyph.rank() => return,
+            Some(own) if own.rank() == glyph.rank() => {}
+            _ => {
+                self.glyph = Some(glyph);
+                self.bits = 0;
+                self.votes.clear();
+            }
+        }
+        self.bits |= match glyph {
+            Glyph::Braille => DOTS[column][row],
+            Glyph::Block => 1 << (row / 2),
+            Glyph::Char(_) => 1,
+        };
+        self.vote(color);
+    }
+
+    fn symbol(&self) -> Option<char> {
+        match self.glyph? {
+            Glyph::Braille => char::from_u32(0x2800 + u32::from(self.bits)),
+            Glyph::Block => Some(HALVES[usize::from(self.bits & 3)]),
+            Glyph::Char(c) => Some(c),
+        }
+    }
+
+    fn vote(&mut self, color: Color) {
+        match self.votes.iter_mut().find(|(c, _)| *c == color) {
+            Some((_, n)) => *n += 1,
+            None => self.votes.push((color, 1)),
+        }
+    }
+
+    fn color(&self) -> Color {
+        self.votes
+            .iter()
+            .max_by_key(|(_, n)| *n)
+            .map_or(Color::Reset, |(c, _)| *c)
+    }
+}
+
+/// Packs particles into cells, each with the glyph it says: braille takes
+/// 2x4 dots to a cell and blocks 1x2, and each cell is in the color most of
+/// its particles have. `x_bounds` and `y_bounds` are the canvas coordinates
+/// covered by the `width` x `height` cells.
+pub fn rasterize(
+    particles: impl Iterator<Item = (f64, f64, Color, Glyph)>,
+    width: u16,
+    height: u16,
+    x_bounds: [f64; 2],
+    y_bounds: [f64; 2],
+) -> Vec<Line<'static>> {
+    let (width, height) = (usize::from(width), usize::from(height));
+    let mut cells = vec![Cell::default(); width * height];
+    let x_scale = 2.0 * width as f64 / (x_bounds[1] - x_bounds[0]);
+    let y_scale = 4.0 * height as f64 / (y_bounds[1] - y_bounds[0]);
+    for (x, y, color, glyph) in particles {
+        let dx = (x - x_bounds[0]) * x_scale;
+        let dy = (y_bounds[1] - y) * y_scale;
+        if dx < 0.0 || dy < 0.0 {
+            continue;
+        }
+        let (dx, dy) = (dx as usize, dy as usize);
+        if dx >= 2 * width || dy >= 4 * height {
+            continue;
+        }
+        cells[dy / 4 * width + dx / 2].light(glyph, dx % 2, dy % 4, color);
+    }
+
+    cells
+        .chunks(width.max(1))
+        .map(|row| {
+            let mut spans: Vec<Span> = Vec::new();
+            let mut run = String::new();
+            let mut run_color = Color::Reset;
+            for cell in row {
+                let (glyph, color) = match cell.symbol() {
+                    Some(glyph) => (glyph, cell.color()),
+                    None => (' ', run_color),
+                };
+                if color != run_color && !run.is_empty() {
+                    spans.push(Span::styled(
+                        std::mem::take(&mut run),
+                        Style::default().fg(run_color),
+                    ));
+                }
+                run_color = color;
+                run.push(glyph);
+            }
+            spans.push(Span::styled(run, Style::default().fg(run_color)));
+            Line::from(spans)
+        })
+        .collect()
+}
+
+/// Lines from `rasterize` drawn over whatever is already there, leaving the
+/// cells without a particle alone.
+pub struct Overlay(pub Vec<Line<'static>>);
+
+impl Widget for Overlay {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        for (row, line) in self.0.iter().enumerate().take(usize::from(area.height)) {
+            let mut column = 0;
+            for span in line.spans.iter() {
+                for glyph in span.content.chars() {
+                    if column >= area.width {
+                        break;
+                    }
+                    if glyph != ' ' {
+                        buf[(area.x + column, area.y + row as u16)]
+                            .set_char(glyph)
+                            .set_style(span.style);
+                    }
+                    column += 1;
+                }
+            }
+        }
+    }
+}
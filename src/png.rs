@@ -0,0 +1,274 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+// where the animation control chunk's frame count goes, just past the
+// signature, the header and its own length and type
+const FRAME_COUNT_AT: u64 = 8 + 25 + 8;
+
+// the lengths deflate can repeat, by the first of each length code and the
+// extra bits that say how far past it
+const LENGTHS: [(usize, u32); 29] = [
+    (3, 0),
+    (4, 0),
+    (5, 0),
+    (6, 0),
+    (7, 0),
+    (8, 0),
+    (9, 0),
+    (10, 0),
+    (11, 1),
+    (13, 1),
+    (15, 1),
+    (17, 1),
+    (19, 2),
+    (23, 2),
+    (27, 2),
+    (31, 2),
+    (35, 3),
+    (43, 3),
+    (51, 3),
+    (59, 3),
+    (67, 4),
+    (83, 4),
+    (99, 4),
+    (115, 4),
+    (131, 5),
+    (163, 5),
+    (195, 5),
+    (227, 5),
+    (258, 0),
+];
+
+/// Saves `pixels`, red, green and blue row by row, as a `width` by `height`
+/// PNG.
+pub fn save(path: &Path, width: u32, height: u32, pixels: &[u8]) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    start(&mut out, width, height)?;
+    chunk(&mut out, b"IDAT", &zlib(&scanlines(width, pixels)))?;
+    chunk(&mut out, b"IEND", &[])?;
+    out.flush()
+}
+
+/// An animated PNG, looping forever, written a frame at a time. Viewers
+/// that don't know APNG show its first frame.
+pub struct Apng {
+    out: BufWriter<File>,
+    width: u32,
+    height: u32,
+    // hundredths of a second each frame stays up
+    delay: u16,
+    frames: u32,
+    // every animation chunk's number, counting up from 0
+    sequence: u32,
+}
+
+impl Apng {
+    pub fn create(path: &Path, width: u32, height: u32, delay: u16) -> io::Result<Apng> {
+        let mut out = BufWriter::new(File::create(path)?);
+        start(&mut out, width, height)?;
+        // the frame count, filled in once it's known, and no end to the plays
+        chunk(&mut out, b"acTL", &[0; 8])?;
+        Ok(Apng {
+            out,
+            width,
+            height,
+            delay,
+            frames: 0,
+            sequence: 0,
+        })
+    }
+
+    /// Adds a frame, red, green and blue row by row.
+    pub fn frame(&mut self, pixels: &[u8]) -> io::Result<()> {
+        let mut control = Vec::with_capacity(26);
+        control.extend_from_slice(&self.next_sequence().to_be_bytes());
+        control.extend_from_slice(&self.width.to_be_bytes());
+        control.extend_from_slice(&self.height.to_be_bytes());
+        // from the top left corner, the delay in hundredths, and replacing
+        // all of the frame before
+        control.extend_from_slice(&[0; 8]);
+        control.extend_from_slice(&self.delay.to_be_bytes());
+        control.extend_from_slice(&100u16.to_be_bytes());
+        control.extend_from_slice(&[0, 0]);
+        chunk(&mut self.out, b"fcTL", &control)?;
+        let data = zlib(&scanlines(self.width, pixels));
+        match self.frames {
+            // the first frame is the still image too
+            0 => chunk(&mut self.out, b"IDAT", &data)?,
+            _ => {
+                let mut frame = self.next_sequence().to_be_bytes().to_vec();
+                frame.extend_from_slice(&data);
+                chunk(&mut self.out, b"fdAT", &frame)?;
+            }
+        }
+        self.frames += 1;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        chunk(&mut self.out, b"IEND", &[])?;
+        let mut control = self.frames.to_be_bytes().to_vec();
+        control.extend_from_slice(&[0; 4]);
+        self.out.seek(SeekFrom::Start(FRAME_COUNT_AT))?;
+        self.out.write_all(&control)?;
+        let crc = crc32(b"acTL".iter().chain(&control));
+        self.out.write_all(&crc.to_be_bytes())?;
+        self.out.flush()
+    }
+
+    fn next_sequence(&mut self) -> u32 {
+        self.sequence += 1;
+        self.sequence - 1
+    }
+}
+
+// the signature and the header of a truecolor image
+fn start(out: &mut impl Write, width: u32, height: u32) -> io::Result<()> {
+    out.write_all(b"\x89PNG\r\n\x1a\n")?;
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // 8 bits a channel, truecolor, the one compression and filter method,
+    // not interlaced
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+    chunk(out, b"IHDR", &header)
+}
+
+// every row after a filter byte saying it only holds what changed since
+// the row above, which leaves the dark sky and the bottom of each dot as
+// runs of zeros
+fn scanlines(width: u32, pixels: &[u8]) -> Vec<u8> {
+    let row = width as usize * 3;
+    let mut data = Vec::with_capacity(pixels.len() + pixels.len() / row.max(1));
+    let mut above: &[u8] = &[];
+    for line in pixels.chunks(row.max(1)) {
+        data.push(2);
+        for (i, &byte) in line.iter().enumerate() {
+            data.push(byte.wrapping_sub(above.get(i).copied().unwrap_or(0)));
+        }
+        above = line;
+    }
+    data
+}
+
+// a zlib stream of one deflate block with the fixed codes, where anything
+// repeating the byte before it goes in as a run
+fn zlib(data: &[u8]) -> Vec<u8> {
+    let mut bits = Bits::default();
+    // the last block, with the fixed codes
+    bits.push(0b011, 3);
+    let mut i = 0;
+    while i < data.len() {
+        let run = match i {
+            0 => 0,
+            _ => data[i..]
+                .iter()
+                .take(258)
+                .take_while(|&&byte| byte == data[i - 1])
+                .count(),
+        };
+        if run < 3 {
+            bits.literal(data[i]);
+            i += 1;
+            continue;
+        }
+        let code = LENGTHS
+            .iter()
+            .rposition(|&(first, _)| first <= run)
+            .unwrap();
+        let (first, extra) = LENGTHS[code];
+        bits.symbol(257 + code as u16);
+        bits.push((run - first) as u32, extra);
+        // a distance of one byte back
+        bits.code(0, 5);
+        i += run;
+    }
+    bits.symbol(256);
+    let mut out = vec![0x78, 0x01];
+    out.extend_from_slice(&bits.finish());
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+// packs values least significant bit first, and codes most significant
+// bit first, as deflate has it
+#[derive(Default)]
+struct Bits {
+    bytes: Vec<u8>,
+    acc: u32,
+    len: u32,
+}
+
+impl Bits {
+    fn push(&mut self, value: u32, size: u32) {
+        self.acc |= value << self.len;
+        self.len += size;
+        while self.len >= 8 {
+            self.bytes.push(self.acc as u8);
+            self.acc >>= 8;
+            self.len -= 8;
+        }
+    }
+
+    fn code(&mut self, code: u16, size: u32) {
+        let reversed = u32::from(code).reverse_bits() >> (32 - size);
+        self.push(reversed, size);
+    }
+
+    fn literal(&mut self, byte: u8) {
+        match byte {
+            0..=143 => self.code(0x30 + u16::from(byte), 8),
+            _ => self.code(0x190 + u16::from(byte - 144), 9),
+        }
+    }
+
+    // the end of the block or a length
+    fn symbol(&mut self, symbol: u16) {
+        match symbol {
+            256..=279 => self.code(symbol - 256, 7),
+            _ => self.code(0xc0 + symbol - 280, 8),
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.len > 0 {
+            self.bytes.push(self.acc as u8);
+        }
+        self.bytes
+    }
+}
+
+fn chunk(out: &mut impl Write, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+    let crc = crc32(kind.iter().chain(data));
+    out.write_all(&crc.to_be_bytes())
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + u32::from(byte)) % 65521;
+        b = (b + a) % 65521;
+    }
+    b << 16 | a
+}
+
+// the CRC every PNG chunk ends with, a bit at a time
+fn crc32<'a>(data: impl Iterator<Item = &'a u8>) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = match crc & 1 {
+                1 => crc >> 1 ^ 0xedb8_8320,
+                _ => crc >> 1,
+            };
+        }
+    }
+    !crc
+}
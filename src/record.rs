@@ -0,0 +1,311 @@
+use crate::config::{Preset, THEMES};
+use crate::keymap::Action;
+use crate::schedule::Launch;
+use firewors::sim::{BurstType, Ground};
+use std::{
+    collections::VecDeque,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+// Sessions are logged one input per line as `<tick> <input>`, where tick is
+// the number of simulation steps taken before the input was handled:
+//
+//     120 launch
+//     340 launch_color 2
+//     350 launch_shape willow
+//     400 attract 12 7
+//     405 launch_at 30 4
+//     410 release
+//     620 beat 85
+//     700 launch x=-0.25 color=3 shape=heart speed=1.2
+//     750 theme pastel
+//     800 resize 80x24
+//     900 quit
+//
+// headed by the seed, the terminal size and the settings the session ran
+// with, named as in a config preset, which a replay needs to come out the
+// same:
+//
+//     # seed 8347192
+//     # size 100x30
+//     # tick_ms 10
+//     # gravity 20
+//     # ground bounce
+//     # wrap true
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Input {
+    Action(Action),
+    // the gravity well follows the mouse, in terminal cells
+    Attract { column: u16, row: u16 },
+    Release,
+    // a rocket that bursts over that cell
+    LaunchAt { column: u16, row: u16 },
+    // a beat in the music, from 0 to 100 as loud as it's been lately
+    Beat { strength: u8 },
+    // a rocket sent up from outside, what it leaves out picked at random
+    Launch(Launch),
+    // the palette changed to a built-in theme, or back to its own with None
+    Theme(Option<&'static str>),
+    // the screen the session goes by, in terminal cells
+    Resize { width: u16, height: u16 },
+}
+
+impl Input {
+    fn to_line(self) -> String {
+        match self {
+            Input::Action(Action::LaunchColor(color)) => format!("launch_color {}", color),
+            Input::Action(Action::LaunchShape(burst)) => format!("launch_shape {}", burst.name()),
+            Input::Action(action) => action.name().to_string(),
+            Input::Attract { column, row } => format!("attract {} {}", column, row),
+            Input::Release => "release".to_string(),
+            Input::LaunchAt { column, row } => format!("launch_at {} {}", column, row),
+            Input::Beat { strength } => format!("beat {}", strength),
+            Input::Launch(launch) => {
+                let mut line = "launch".to_string();
+                if let Some(x) = launch.x {
+                    line += &format!(" x={}", x);
+                }
+                if let Some(color) = launch.color {
+                    line += &format!(" color={}", color + 1);
+                }
+                if let Some(burst) = launch.burst {
+                    line += &format!(" shape={}", burst.name());
+                }
+                if let Some(speed) = launch.speed {
+                    line += &format!(" speed={}", speed);
+                }
+                line
+            }
+            Input::Theme(Some(name)) => format!("theme {}", name),
+            Input::Theme(None) => "theme".to_string(),
+            Input::Resize { width, height } => format!("resize {}x{}", width, height),
+        }
+    }
+
+    /// Reads an input the way it is logged, without the tick.
+    pub fn parse(words: &[&str]) -> Option<Input> {
+        match words {
+            ["launch_color", color] => {
+                Some(Input::Action(Action::LaunchColor(color.parse().ok()?)))
+            }
+            ["launch_shape", name] => BurstType::from_name(name)
+                .map(Action::LaunchShape)
+                .map(Input::Action),
+            ["attract", column, row] => Some(Input::Attract {
+                column: column.parse().ok()?,
+                row: row.parse().ok()?,
+            }),
+            ["release"] => Some(Input::Release),
+            ["launch_at", column, row] => Some(Input::LaunchAt {
+                column: column.parse().ok()?,
+                row: row.parse().ok()?,
+            }),
+            ["beat", strength] => Some(Input::Beat {
+                strength: strength.parse().ok()?,
+            }),
+            ["theme"] => Some(Input::Theme(None)),
+            ["theme", name] => THEMES
+                .iter()
+                .find(|(theme, _)| theme == name)
+                .map(|(theme, _)| Input::Theme(Some(theme))),
+            ["resize", size] => {
+                let (width, height) = parse_size(size)?;
+                Some(Input::Resize { width, height })
+            }
+            [name] => Action::from_name(name).map(Input::Action),
+            ["launch", settings @ ..] => parse_launch(settings).map(Input::Launch),
+            _ => None,
+        }
+    }
+}
+
+/// Where `x=` may put a launch, as a share of the screen width from its
+/// middle: the left edge to the right one.
+pub const X_RANGE: std::ops::RangeInclusive<f64> = -0.5..=0.5;
+
+// `key=value` settings as in a show file, the color by its number key
+fn parse_launch(settings: &[&str]) -> Option<Launch> {
+    let mut launch = Launch {
+        x: None,
+        color: None,
+        burst: None,
+        speed: None,
+    };
+    for setting in settings {
+        match setting.split_once('=')? {
+            ("x", x) => launch.x = Some(x.parse().ok().filter(|x| X_RANGE.contains(x))?),
+            ("color", key) => launch.color = Some(key.parse::<usize>().ok()?.checked_sub(1)?),
+            ("shape", name) => launch.burst = Some(BurstType::from_name(name)?),
+            ("speed", speed) => {
+                launch.speed = Some(
+                    speed
+                        .parse()
+                        .ok()
+                        .filter(|s: &f64| *s > 0.0 && s.is_finite())?,
+                )
+            }
+            _ => return None,
+        }
+    }
+    Some(launch)
+}
+
+fn parse_size(size: &str) -> Option<(u16, u16)> {
+    let (width, height) = size.split_once('x')?;
+    let size = (width.parse().ok()?, height.parse().ok()?);
+    // nothing could be drawn on it
+    Some(size).filter(|&(width, height)| width > 0 && height > 0)
+}
+
+pub struct Recorder {
+    out: BufWriter<File>,
+}
+
+impl Recorder {
+    /// Starts a log for a session seeded with `seed` on a screen `size`
+    /// cells large, going by `settings`.
+    pub fn create(
+        path: &Path,
+        seed: Option<u64>,
+        size: (u16, u16),
+        settings: &Preset,
+    ) -> io::Result<Recorder> {
+        let mut out = BufWriter::new(File::create(path)?);
+        if let Some(seed) = seed {
+            writeln!(out, "# seed {}", seed)?;
+        }
+        writeln!(out, "# size {}x{}", size.0, size.1)?;
+        for (name, value) in header_settings(settings) {
+            writeln!(out, "# {} {}", name, value)?;
+        }
+        Ok(Recorder { out })
+    }
+
+    pub fn log(&mut self, tick: u64, input: Input) -> io::Result<()> {
+        writeln!(self.out, "{} {}", tick, input.to_line())
+    }
+}
+
+// the settings that change how a session plays out, those it was given
+fn header_settings(settings: &Preset) -> Vec<(&'static str, String)> {
+    let mut header = Vec::new();
+    let mut add = |name, value: Option<String>| {
+        if let Some(value) = value {
+            header.push((name, value));
+        }
+    };
+    add("tick_ms", settings.tick_ms.map(|n| n.to_string()));
+    add("gravity", settings.gravity.map(|x| x.to_string()));
+    add("drag", settings.drag.map(|x| x.to_string()));
+    add("wind", settings.wind.map(|x| x.to_string()));
+    add("turbulence", settings.turbulence.map(|x| x.to_string()));
+    add("particles", settings.particles.map(|n| n.to_string()));
+    add("subparticles", settings.subparticles.map(|n| n.to_string()));
+    add("launch_speed", settings.launch_speed.map(|x| x.to_string()));
+    add("burst_scale", settings.burst_scale.map(|x| x.to_string()));
+    add(
+        "shell_sizes",
+        settings
+            .shell_sizes
+            .map(|[min, max]| format!("{},{}", min, max)),
+    );
+    add("apex_fuzz", settings.apex_fuzz.map(|x| x.to_string()));
+    add("depth", settings.depth.map(|n| n.to_string()));
+    add("ground", settings.ground.map(|g| g.name().to_string()));
+    add("wrap", settings.wrap.map(|on| on.to_string()));
+    header
+}
+
+pub struct Replay {
+    inputs: VecDeque<(u64, Input)>,
+    /// What the session was seeded with, if the log says.
+    pub seed: Option<u64>,
+    /// The terminal size it was recorded in, if the log says.
+    pub size: Option<(u16, u16)>,
+    /// The settings it was recorded with, as far as the log says.
+    pub settings: Preset,
+}
+
+impl Replay {
+    /// Reads a session log, skipping (and warning about) lines it can't make
+    /// sense of.
+    pub fn load(path: &Path) -> io::Result<Replay> {
+        let mut replay = Replay {
+            inputs: VecDeque::new(),
+            seed: None,
+            size: None,
+            settings: Preset::default(),
+        };
+        for (n, line) in fs::read_to_string(path)?.lines().enumerate() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            if words.is_empty() {
+                continue;
+            }
+            if let Some(header) = line.strip_prefix('#') {
+                replay.read_header(header);
+                continue;
+            }
+            let tick = words[0].parse::<u64>().ok();
+            match (tick, Input::parse(&words[1..])) {
+                (Some(tick), Some(input)) => replay.inputs.push_back((tick, input)),
+                _ => eprintln!(
+                    "firewors: {}:{}: skipping `{}`",
+                    path.display(),
+                    n + 1,
+                    line
+                ),
+            }
+        }
+        // what a config preset couldn't have, a recording can't either
+        replay
+            .settings
+            .check()
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        Ok(replay)
+    }
+
+    // anything but the seed, the size and the settings is a comment
+    fn read_header(&mut self, header: &str) {
+        let words: Vec<&str> = header.split_whitespace().collect();
+        let settings = &mut self.settings;
+        match words[..] {
+            ["seed", seed] => self.seed = seed.parse().ok().or(self.seed),
+            ["size", size] => self.size = parse_size(size).or(self.size),
+            ["tick_ms", n] => settings.tick_ms = n.parse().ok(),
+            ["gravity", x] => settings.gravity = x.parse().ok(),
+            ["drag", x] => settings.drag = x.parse().ok(),
+            ["wind", x] => settings.wind = x.parse().ok(),
+            ["turbulence", x] => settings.turbulence = x.parse().ok(),
+            ["particles", n] => settings.particles = n.parse().ok(),
+            ["subparticles", n] => settings.subparticles = n.parse().ok(),
+            ["launch_speed", x] => settings.launch_speed = x.parse().ok(),
+            ["burst_scale", x] => settings.burst_scale = x.parse().ok(),
+            ["shell_sizes", sizes] => {
+                settings.shell_sizes = sizes
+                    .split_once(',')
+                    .and_then(|(min, max)| Some([min.parse().ok()?, max.parse().ok()?]));
+            }
+            ["apex_fuzz", x] => settings.apex_fuzz = x.parse().ok(),
+            ["depth", n] => settings.depth = n.parse().ok(),
+            ["ground", name] => settings.ground = Ground::from_name(name),
+            ["wrap", on] => settings.wrap = on.parse().ok(),
+            _ => {}
+        }
+    }
+
+    /// The inputs that were handled before step `tick`, in order.
+    pub fn due(&mut self, tick: u64) -> Vec<Input> {
+        let mut due = Vec::new();
+        while let Some((at, input)) = self.inputs.front() {
+            if *at > tick {
+                break;
+            }
+            due.push(*input);
+            self.inputs.pop_front();
+        }
+        due
+    }
+}
@@ -0,0 +1,177 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    widgets::Widget,
+};
+use serde::Deserialize;
+
+// the same town and sky every time for a given size
+const SEED: u64 = 0x5ca1ab1e;
+// cells of sky per star, and the share of it they are spread over
+const CELLS_PER_STAR: usize = 60;
+const STARRY_SHARE: f64 = 0.6;
+// frames a star holds its brightness before it may change
+const TWINKLE_FRAMES: u64 = 12;
+// widest building, in cells, and tallest as a share of the screen
+const MAX_BUILDING: u16 = 9;
+const MAX_BUILDING_SHARE: f64 = 0.2;
+const LIT_WINDOWS: f64 = 0.15;
+
+const BUILDING: Color = Color::Rgb(22, 22, 34);
+const WINDOW: Color = Color::Rgb(210, 175, 90);
+const STAR: Color = Color::Rgb(150, 150, 170);
+const MOON: Color = Color::Rgb(235, 230, 200);
+
+/// The background layers to draw behind the fireworks, from the
+/// `[scenery]` table of the config file.
+#[derive(Deserialize, Default, Debug, Clone, Copy)]
+#[serde(default, deny_unknown_fields)]
+pub struct Layers {
+    pub skyline: bool,
+    pub stars: bool,
+    pub moon: bool,
+}
+
+impl Layers {
+    pub fn all() -> Layers {
+        Layers {
+            skyline: true,
+            stars: true,
+            moon: true,
+        }
+    }
+}
+
+/// A still backdrop of stars, a moon and a town, laid out to the screen.
+pub struct Scenery {
+    layers: Layers,
+    size: Rect,
+    // counts frames, for the twinkle
+    frame: u64,
+    stars: Vec<(u16, u16)>,
+    // the height of the town in every column, and its lit windows
+    skyline: Vec<u16>,
+    windows: Vec<(u16, u16)>,
+}
+
+impl Scenery {
+    pub fn new(layers: Layers) -> Scenery {
+        Scenery {
+            layers,
+            size: Rect::default(),
+            frame: 0,
+            stars: Vec::new(),
+            skyline: Vec::new(),
+            windows: Vec::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !(self.layers.skyline || self.layers.stars || self.layers.moon)
+    }
+
+    // lays everything out again for a new size and moves the twinkle on
+    pub fn next_frame(&mut self, size: Rect) {
+        self.frame += 1;
+        if size == self.size {
+            return;
+        }
+        self.size = size;
+        let mut rng =
+            StdRng::seed_from_u64(SEED ^ (u64::from(size.width) << 16) ^ u64::from(size.height));
+        let sky = (f64::from(size.height) * STARRY_SHARE) as u16;
+        let stars = usize::from(size.width) * usize::from(sky) / CELLS_PER_STAR;
+        self.stars = (0..stars)
+            .map(|_| (rng.gen_range(0..size.width), rng.gen_range(0..sky.max(1))))
+            .collect();
+
+        self.skyline = Vec::with_capacity(usize::from(size.width));
+        self.windows.clear();
+        let tallest = ((f64::from(size.height) * MAX_BUILDING_SHARE) as u16).max(2);
+        while self.skyline.len() < usize::from(size.width) {
+            // now and then a gap between blocks
+            let gap = if rng.gen_bool(0.3) {
+                rng.gen_range(1..3)
+            } else {
+                0
+            };
+            self.skyline.extend((0..gap).map(|_| 0));
+            let height = rng.gen_range(1..=tallest);
+            let start = self.skyline.len() as u16;
+            for x in start..start + rng.gen_range(3..=MAX_BUILDING) {
+                // the top row is roof
+                for floor in 1..height {
+                    if rng.gen_bool(LIT_WINDOWS) {
+                        self.windows.push((x, floor));
+                    }
+                }
+                self.skyline.push(height);
+            }
+        }
+        self.skyline.truncate(usize::from(size.width));
+    }
+
+    fn star_glyph(&self, i: usize) -> (char, Color) {
+        // the same dice for every frame of a stretch, so stars hold a while
+        let mut rng =
+            StdRng::seed_from_u64(SEED ^ ((i as u64) << 20) ^ (self.frame / TWINKLE_FRAMES));
+        match rng.gen_range(0..20) {
+            0 => ('+', Color::White),
+            1 | 2 => (' ', STAR),
+            _ => ('·', STAR),
+        }
+    }
+}
+
+/// Draws a `Scenery`, with `color` picking what each of its colors comes
+/// out as.
+pub struct Backdrop<'a, F: Fn(Color) -> Color> {
+    pub scenery: &'a Scenery,
+    pub color: F,
+}
+
+impl<F: Fn(Color) -> Color> Widget for Backdrop<'_, F> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let scenery = self.scenery;
+        let layers = scenery.layers;
+        let mut put = |x: u16, y: u16, glyph: char, style: Style| {
+            if x < area.width && y < area.height {
+                buf[(area.x + x, area.y + y)]
+                    .set_char(glyph)
+                    .set_style(style);
+            }
+        };
+        if layers.stars {
+            for (i, (x, y)) in scenery.stars.iter().enumerate() {
+                let (glyph, color) = scenery.star_glyph(i);
+                put(*x, *y, glyph, Style::default().fg((self.color)(color)));
+            }
+        }
+        if layers.moon && area.width >= 20 {
+            let moon = Style::default().fg((self.color)(MOON));
+            let x = area.width - area.width / 6;
+            for (dx, glyph) in "▄██▄".chars().enumerate() {
+                put(x + dx as u16, 1, glyph, moon);
+            }
+            for (dx, glyph) in "▀██▀".chars().enumerate() {
+                put(x + dx as u16, 2, glyph, moon);
+            }
+        }
+        if layers.skyline {
+            // the background too, so sparks drawn over it keep the town
+            let color = (self.color)(BUILDING);
+            let building = Style::default().fg(color).bg(color);
+            for (x, height) in scenery.skyline.iter().enumerate() {
+                for floor in 0..(*height).min(area.height) {
+                    put(x as u16, area.height - 1 - floor, '█', building);
+                }
+            }
+            let window = building.fg((self.color)(WINDOW));
+            for (x, floor) in scenery.windows.iter() {
+                put(*x, area.height.saturating_sub(1 + floor), '▪', window);
+            }
+        }
+    }
+}
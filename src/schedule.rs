@@ -0,0 +1,51 @@
+use firewors::sim::BurstType;
+
+/// A rocket planned ahead of time, by a show file or the finale.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Launch {
+    /// across the screen, -0.5 at the left edge and 0.5 at the right
+    pub x: Option<f64>,
+    pub color: Option<usize>,
+    pub burst: Option<BurstType>,
+    /// share of the usual top launch speed
+    pub speed: Option<f64>,
+}
+
+/// Launches queued up for later, in simulation time.
+pub struct Schedule {
+    // seconds a tick takes
+    tick: f64,
+    // the last tick `due` was asked about
+    now: u64,
+    // earliest last, so the next one pops off the end
+    queue: Vec<(u64, Launch)>,
+}
+
+impl Schedule {
+    pub fn new(tick: f64) -> Schedule {
+        Schedule {
+            tick,
+            now: 0,
+            queue: Vec::new(),
+        }
+    }
+
+    /// Queues `launch` to go up `delay` seconds from now.
+    pub fn push(&mut self, delay: f64, launch: Launch) {
+        let at = self.now + (delay / self.tick).round() as u64;
+        let i = self.queue.partition_point(|(t, _)| *t > at);
+        self.queue.insert(i, (at, launch));
+    }
+
+    /// The launches due before step `tick`.
+    pub fn due(&mut self, tick: u64) -> Vec<Launch> {
+        self.now = tick;
+        let mut due = Vec::new();
+        while self.queue.last().is_some_and(|(at, _)| *at <= tick) {
+            if let Some((_, launch)) = self.queue.pop() {
+                due.push(launch);
+            }
+        }
+        due
+    }
+}
@@ -0,0 +1,78 @@
+use crate::countdown::timestamp;
+use crate::export::{image_size, rasterize, DOT};
+use crate::{ansi, png, App};
+use ratatui::{buffer::Buffer, style::Modifier};
+use std::{
+    fmt::Write as _,
+    fs, io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// Saves what is on screen into `dir`: `buffer` as a text file of ANSI
+/// escape codes, which `cat` shows as it was, and the sparks in it as a
+/// PNG, drawn the way `export` draws them. Both are named after the local
+/// time, and the name they share comes back without its extension.
+pub fn save(app: &App, buffer: &Buffer, dir: &Path) -> io::Result<PathBuf> {
+    let stamp = format!("firewors-{}", timestamp(SystemTime::now()));
+    // a second shot within the same second doesn't replace the first
+    let mut name = dir.join(&stamp);
+    let mut n = 1;
+    while name.with_extension("ans").exists() || name.with_extension("png").exists() {
+        n += 1;
+        name = dir.join(format!("{}-{}", stamp, n));
+    }
+    fs::write(name.with_extension("ans"), ansi_text(buffer))?;
+
+    let (width, height) = (buffer.area.width, buffer.area.height);
+    let (image_width, image_height) = image_size(width, height);
+    let mut pixels = vec![0; image_width * image_height * 3];
+    rasterize(app, width, height, |dx, dy, color| {
+        let (r, g, b) = ansi::to_rgb(color);
+        for row in dy..dy + DOT {
+            for column in dx..dx + DOT {
+                let i = (row * image_width + column) * 3;
+                pixels[i..i + 3].copy_from_slice(&[r, g, b]);
+            }
+        }
+    });
+    png::save(
+        &name.with_extension("png"),
+        image_width as u32,
+        image_height as u32,
+        &pixels,
+    )?;
+    Ok(name)
+}
+
+// the buffer row by row, a new escape code wherever the style changes
+fn ansi_text(buffer: &Buffer) -> String {
+    let mut text = String::new();
+    let area = buffer.area;
+    for y in area.top()..area.bottom() {
+        let mut last = None;
+        for x in area.left()..area.right() {
+            let cell = &buffer[(x, y)];
+            let style = (cell.fg, cell.bg, cell.modifier);
+            if last != Some(style) {
+                let _ = write!(
+                    text,
+                    "\x1b[0;{};{}",
+                    ansi::sgr(cell.fg, false),
+                    ansi::sgr(cell.bg, true)
+                );
+                if cell.modifier.contains(Modifier::BOLD) {
+                    text.push_str(";1");
+                }
+                if cell.modifier.contains(Modifier::DIM) {
+                    text.push_str(";2");
+                }
+                text.push('m');
+                last = Some(style);
+            }
+            text.push_str(cell.symbol());
+        }
+        text.push_str("\x1b[0m\n");
+    }
+    text
+}
@@ -0,0 +1,144 @@
+use crate::config::parse_color;
+use crate::error::Error;
+use crate::schedule::Launch;
+use firewors::sim::BurstType;
+use ratatui::style::Color;
+use serde::Deserialize;
+use std::{fs, path::Path};
+
+// A show is a TOML file with one [[launch]] table per rocket:
+//
+//     loop = true
+//
+//     [[launch]]
+//     at = 0.5
+//     x = -0.25
+//     color = "red"
+//     shape = "heart"
+//
+// Everything but `at` can be left out to pick it at random.
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ShowFile {
+    #[serde(default, rename = "loop")]
+    looped: bool,
+    #[serde(default)]
+    launch: Vec<LaunchEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LaunchEntry {
+    at: f64,
+    x: Option<f64>,
+    color: Option<ColorEntry>,
+    shape: Option<String>,
+    speed: Option<f64>,
+}
+
+// the number key the color is on, or the color itself
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ColorEntry {
+    Key(usize),
+    Name(String),
+}
+
+/// Launches read from a show file, handed out as the simulation reaches them.
+pub struct Show {
+    // in ticks from the start of the show, earliest first
+    launches: Vec<(u64, Launch)>,
+    looped: bool,
+    start: u64,
+    next: usize,
+}
+
+impl Show {
+    /// Reads a show, checking its colors against `palette`; `tick` is the
+    /// length of a simulation step in seconds.
+    pub fn load(path: &Path, palette: &[Color], tick: f64) -> Result<Show, Error> {
+        let in_file = |err: String| Error::Show(format!("{}: {}", path.display(), err));
+        let text = fs::read_to_string(path).map_err(Error::open(path.display()))?;
+        let file: ShowFile = toml::from_str(&text).map_err(|err| in_file(err.to_string()))?;
+
+        let mut launches = Vec::new();
+        for entry in file.launch {
+            if !(entry.at >= 0.0 && entry.at.is_finite()) {
+                return Err(in_file(format!("`at = {}` is not a time", entry.at)));
+            }
+            if entry
+                .speed
+                .is_some_and(|speed| !(speed > 0.0 && speed.is_finite()))
+            {
+                return Err(in_file("`speed` needs to be positive".to_string()));
+            }
+            let color = match entry.color {
+                None => None,
+                Some(ColorEntry::Key(key)) if (1..=palette.len()).contains(&key) => Some(key - 1),
+                Some(ColorEntry::Key(key)) => {
+                    return Err(in_file(format!("there is no color {}", key)))
+                }
+                Some(ColorEntry::Name(name)) => {
+                    let color = parse_color(&name);
+                    let index = palette.iter().position(|c| Some(*c) == color);
+                    match index {
+                        Some(index) => Some(index),
+                        None => return Err(in_file(format!("`{}` is not in the palette", name))),
+                    }
+                }
+            };
+            let burst = match entry.shape {
+                None => None,
+                Some(name) => Some(
+                    BurstType::from_name(&name)
+                        .ok_or_else(|| in_file(format!("unknown shape `{}`", name)))?,
+                ),
+            };
+            let at = (entry.at / tick).round() as u64;
+            launches.push((
+                at,
+                Launch {
+                    x: entry.x.map(|x| x.clamp(-0.5, 0.5)),
+                    color,
+                    burst,
+                    speed: entry.speed,
+                },
+            ));
+        }
+        launches.sort_by_key(|(at, _)| *at);
+
+        Ok(Show {
+            launches,
+            looped: file.looped,
+            start: 0,
+            next: 0,
+        })
+    }
+
+    /// The launches due before step `tick`.
+    pub fn due(&mut self, tick: u64) -> Vec<Launch> {
+        let mut due = Vec::new();
+        while let Some((at, launch)) = self.launches.get(self.next) {
+            if self.start + at > tick {
+                break;
+            }
+            due.push(*launch);
+            self.next += 1;
+        }
+        due
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.next >= self.launches.len()
+    }
+
+    /// Starts a looping show over from `tick`, telling whether it loops.
+    pub fn rewind(&mut self, tick: u64) -> bool {
+        if self.looped {
+            self.start = tick;
+            self.next = 0;
+        }
+        self.looped
+    }
+}
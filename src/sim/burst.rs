@@ -0,0 +1,249 @@
+use super::{
+    effect::{Effect, EffectId, DRAG, EMBER_LIFETIME, SPARKLE},
+    vector::{random_unit_vector, Vector},
+    well::Well,
+};
+use rand::{rngs::StdRng, Rng};
+use std::f64::consts::PI;
+
+// the well a vortex leaves where it burst, pulling its embers back in as
+// it whirls them around
+const VORTEX: Well = Well {
+    pos: Vector { x: 0.0, y: 0.0 },
+    strength: 70.0,
+    spin: 45.0,
+    radius: f64::INFINITY,
+};
+
+// the same for text, which falls at g / ln(1 / MESSAGE_DRAG), about seven
+// cells a second
+pub(super) const MESSAGE_DRAG: f64 = 0.002;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BurstType {
+    Peony,
+    Ring,
+    Spiral,
+    Heart,
+    // a five-pointed star, and a smiling face; like the heart they keep
+    // upright until gravity pulls them apart
+    Star,
+    Smiley,
+    // slow embers that hang and droop for a long time
+    Willow,
+    // a handful of heavy arms thrown upwards
+    Palm,
+    // a dense peony whose embers burn longer
+    Chrysanthemum,
+    // a few stars that each split into a cross of four
+    Crossette,
+    // a ring that whirls around where it burst and falls back into it
+    Vortex,
+    // embers laid out along the text set with `World::set_message`; never
+    // picked at random
+    Message,
+    /// An effect from `World::register_effect`; anywhere else it bursts
+    /// like a peony.
+    Custom(EffectId),
+}
+
+pub(super) const BURSTS: [BurstType; 11] = [
+    BurstType::Peony,
+    BurstType::Ring,
+    BurstType::Spiral,
+    BurstType::Heart,
+    BurstType::Star,
+    BurstType::Smiley,
+    BurstType::Willow,
+    BurstType::Palm,
+    BurstType::Chrysanthemum,
+    BurstType::Crossette,
+    BurstType::Vortex,
+];
+
+impl BurstType {
+    /// The shapes a rocket bursts into at random, everything but a message
+    /// and the custom effects.
+    pub const SHAPES: [BurstType; 11] = BURSTS;
+
+    /// The burst called `name` in lower case, `peony` to `message`.
+    pub fn from_name(name: &str) -> Option<BurstType> {
+        match name {
+            "peony" => Some(BurstType::Peony),
+            "ring" => Some(BurstType::Ring),
+            "spiral" => Some(BurstType::Spiral),
+            "heart" => Some(BurstType::Heart),
+            "star" => Some(BurstType::Star),
+            "smiley" => Some(BurstType::Smiley),
+            "willow" => Some(BurstType::Willow),
+            "palm" => Some(BurstType::Palm),
+            "chrysanthemum" => Some(BurstType::Chrysanthemum),
+            "crossette" => Some(BurstType::Crossette),
+            "vortex" => Some(BurstType::Vortex),
+            "message" => Some(BurstType::Message),
+            _ => None,
+        }
+    }
+
+    /// What `from_name` (or `World::burst_named`, for a custom one) knows
+    /// the burst by.
+    pub fn name(self) -> &'static str {
+        match self {
+            BurstType::Peony => "peony",
+            BurstType::Ring => "ring",
+            BurstType::Spiral => "spiral",
+            BurstType::Heart => "heart",
+            BurstType::Star => "star",
+            BurstType::Smiley => "smiley",
+            BurstType::Willow => "willow",
+            BurstType::Palm => "palm",
+            BurstType::Chrysanthemum => "chrysanthemum",
+            BurstType::Crossette => "crossette",
+            BurstType::Vortex => "vortex",
+            BurstType::Message => "message",
+            BurstType::Custom(id) => id.name,
+        }
+    }
+
+    pub(super) fn random(rng: &mut StdRng) -> BurstType {
+        BURSTS[rng.gen_range(0..BURSTS.len())]
+    }
+}
+
+impl Effect for BurstType {
+    fn particle_count(&self) -> usize {
+        match self {
+            BurstType::Peony | BurstType::Message | BurstType::Custom(_) => 19,
+            BurstType::Ring | BurstType::Spiral | BurstType::Vortex => 24,
+            BurstType::Heart | BurstType::Willow => 32,
+            BurstType::Palm => 7,
+            BurstType::Crossette => 8,
+            BurstType::Chrysanthemum | BurstType::Star => 40,
+            BurstType::Smiley => 44,
+        }
+    }
+
+    fn min_depth(&self) -> u8 {
+        match self {
+            BurstType::Crossette => 2,
+            _ => 1,
+        }
+    }
+
+    fn drag(&self) -> f64 {
+        match self {
+            BurstType::Willow => 0.02,
+            BurstType::Palm => 0.35,
+            // stops almost at once, so the text holds together while it falls
+            BurstType::Message => MESSAGE_DRAG,
+            _ => DRAG,
+        }
+    }
+
+    fn trail_len(&self) -> usize {
+        match self {
+            BurstType::Willow => 6,
+            BurstType::Palm | BurstType::Chrysanthemum | BurstType::Vortex => 4,
+            _ => 2,
+        }
+    }
+
+    fn sparkle(&self) -> f64 {
+        match self {
+            // the long-burning ones go out in a shower of glitter
+            BurstType::Willow => 1.5,
+            BurstType::Chrysanthemum => 1.0,
+            _ => SPARKLE,
+        }
+    }
+
+    fn lifetime(&self, rng: &mut StdRng) -> f64 {
+        let [min, max] = match self {
+            BurstType::Willow => [2.5, 3.5],
+            BurstType::Palm => [1.4, 2.0],
+            BurstType::Chrysanthemum | BurstType::Vortex => [2.2, 3.0],
+            BurstType::Message => [2.5, 3.5],
+            _ => EMBER_LIFETIME,
+        };
+        rng.gen_range(min..max)
+    }
+
+    fn field(&self, _age: f64, offset: Vector) -> Vector {
+        match self {
+            BurstType::Vortex => VORTEX.force(offset),
+            _ => Vector::zero(),
+        }
+    }
+
+    fn velocity(&self, i: usize, n: usize, offset: f64, rng: &mut StdRng) -> Vector {
+        let t = i as f64 / n as f64;
+        let angle = offset + 2.0 * PI * t;
+        let dir = Vector {
+            x: angle.cos(),
+            y: angle.sin(),
+        };
+        match self {
+            BurstType::Peony | BurstType::Custom(_) => {
+                random_unit_vector(rng) * rng.gen_range(20.0..40.0)
+            }
+            BurstType::Ring => dir * 30.0,
+            BurstType::Spiral => dir * (10.0 + 30.0 * t),
+            BurstType::Heart => {
+                let t = 2.0 * PI * t;
+                // x = 16sin^3(t), y = 13cos(t) - 5cos(2t) - 2cos(3t) - cos(4t)
+                Vector {
+                    x: 16.0 * t.sin().powi(3),
+                    y: 13.0 * t.cos()
+                        - 5.0 * (2.0 * t).cos()
+                        - 2.0 * (3.0 * t).cos()
+                        - (4.0 * t).cos(),
+                } * (40.0 / 17.0)
+            }
+            BurstType::Star => {
+                // along the ten edges between the tips and the notches
+                let edge = t * 10.0;
+                let corner = |k: f64| {
+                    let angle = PI / 2.0 + k * PI / 5.0;
+                    let radius = if k % 2.0 == 0.0 { 32.0 } else { 13.0 };
+                    Vector {
+                        x: angle.cos(),
+                        y: angle.sin(),
+                    } * radius
+                };
+                let (k, along) = (edge.floor(), edge.fract());
+                corner(k) * (1.0 - along) + corner(k + 1.0) * along
+            }
+            BurstType::Smiley => {
+                let on = |angle: f64| Vector {
+                    x: angle.cos(),
+                    y: angle.sin(),
+                };
+                // the face takes half the embers, the mouth a quarter and
+                // each eye an eighth
+                match t {
+                    t if t < 0.5 => on(2.0 * PI * t / 0.5) * 30.0,
+                    t if t < 0.75 => on(PI * (1.2 + 0.6 * (t - 0.5) / 0.25)) * 18.0,
+                    t => {
+                        let side = if t < 0.875 { -10.0 } else { 10.0 };
+                        Vector { x: side, y: 10.0 } + on(2.0 * PI * (t * 8.0).fract()) * 2.0
+                    }
+                }
+            }
+            BurstType::Willow => random_unit_vector(rng) * rng.gen_range(25.0..45.0),
+            BurstType::Palm => {
+                // evenly spread over the upper half, a little ragged
+                let angle = PI * (t + 0.5 / n as f64) + rng.gen_range(-0.1..0.1);
+                Vector {
+                    x: angle.cos(),
+                    y: angle.sin(),
+                } * 25.0
+                    + Vector { x: 0.0, y: 8.0 }
+            }
+            BurstType::Chrysanthemum => random_unit_vector(rng) * rng.gen_range(25.0..40.0),
+            BurstType::Crossette => dir * rng.gen_range(25.0..30.0),
+            BurstType::Vortex => dir * rng.gen_range(25.0..32.0),
+            // laid out by the world, which knows the text
+            BurstType::Message => dir,
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use ratatui::style::Color;
+
+pub const DEFAULT_PALETTE: [Color; 6] = [
+    Color::Blue,
+    Color::Green,
+    Color::Magenta,
+    Color::Red,
+    Color::Yellow,
+    Color::White,
+];
+// ages (in seconds) at which an ember has cooled from white to its hue,
+// starts to cool down from it and has turned to EMBER; after that it goes
+// on to ASH until it burns out
+pub(super) const FADE_AGES: [f64; 3] = [0.1, 0.6, 1.2];
+const WHITE: (u8, u8, u8) = (255, 255, 255);
+const EMBER: (u8, u8, u8) = (120, 45, 10);
+const ASH: (u8, u8, u8) = (50, 50, 50);
+
+fn rgb(color: Color) -> (u8, u8, u8) {
+    match color {
+        Color::Rgb(r, g, b) => (r, g, b),
+        Color::Red | Color::LightRed => (255, 60, 60),
+        Color::Green | Color::LightGreen => (60, 255, 60),
+        Color::Yellow | Color::LightYellow => (255, 230, 60),
+        Color::Blue | Color::LightBlue => (70, 110, 255),
+        Color::Magenta | Color::LightMagenta => (255, 60, 255),
+        Color::Cyan | Color::LightCyan => (60, 255, 255),
+        _ => (255, 255, 255),
+    }
+}
+
+fn blend(a: (u8, u8, u8), b: (u8, u8, u8), t: f64) -> Color {
+    let t = t.clamp(0.0, 1.0);
+    let mix = |a: u8, b: u8| (f64::from(a) * (1.0 - t) + f64::from(b) * t) as u8;
+    Color::Rgb(mix(a.0, b.0), mix(a.1, b.1), mix(a.2, b.2))
+}
+
+/// `color` made brighter, towards white, for a `brightness` above 1 and
+/// darker for one below.
+pub(super) fn glint(color: Color, brightness: f64) -> Color {
+    let (r, g, b) = rgb(color);
+    if brightness >= 1.0 {
+        blend((r, g, b), WHITE, brightness - 1.0)
+    } else {
+        blend((0, 0, 0), (r, g, b), brightness)
+    }
+}
+
+/// The color of an ember of hue `color` that is `age` seconds into a life
+/// of `lifetime`: white-hot, then the hue itself, cooling through a dim
+/// ember tone to gray.
+pub(super) fn fade(color: Color, age: f64, lifetime: f64) -> Color {
+    let [hot, warm, cool] = FADE_AGES;
+    if age < hot {
+        blend(WHITE, rgb(color), age / hot)
+    } else if age < warm {
+        // kept as is, so it still is one of the 16 colors if it was
+        color
+    } else if age < cool {
+        blend(rgb(color), EMBER, (age - warm) / (cool - warm))
+    } else if lifetime > cool {
+        blend(EMBER, ASH, (age - cool) / (lifetime - cool))
+    } else {
+        blend(EMBER, ASH, 1.0)
+    }
+}
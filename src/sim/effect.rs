@@ -0,0 +1,109 @@
+use super::{color::fade, vector::Vector};
+use rand::{rngs::StdRng, Rng};
+use ratatui::style::Color;
+
+// fraction of an ember's speed left after one second of drag (0.98 per
+// REFERENCE_DT)
+pub(super) const DRAG: f64 = 0.1326;
+
+// how long embers burn, in seconds
+pub(super) const EMBER_LIFETIME: [f64; 2] = [1.8, 2.6];
+
+// how long before burning out they start to glitter
+pub(super) const SPARKLE: f64 = 0.5;
+
+/// How a burst throws its embers out, moves them, colors them and burns
+/// them out. Every `BurstType` is one; implement it for a shape of your own
+/// and hand it to `World::register_effect`.
+///
+/// ```
+/// use firewors::sim::{Effect, Vector, World, REFERENCE_DT};
+/// use rand::rngs::StdRng;
+///
+/// // embers thrown out in a ring that curl around as they slow down
+/// struct Whirl;
+///
+/// impl Effect for Whirl {
+///     fn particle_count(&self) -> usize {
+///         24
+///     }
+///
+///     fn velocity(&self, i: usize, n: usize, offset: f64, _: &mut StdRng) -> Vector {
+///         let angle = offset + std::f64::consts::TAU * i as f64 / n as f64;
+///         Vector { x: angle.cos(), y: angle.sin() } * 30.0
+///     }
+///
+///     fn force(&self, _age: f64, vel: Vector) -> Vector {
+///         Vector { x: -vel.y, y: vel.x } * 3.0
+///     }
+/// }
+///
+/// let mut world = World::new();
+/// let whirl = world.register_effect("whirl", Whirl);
+/// world.launch_burst(0, Vector::zero(), Vector { x: 0.0, y: 40.0 }, whirl);
+/// for _ in 0..150 {
+///     world.step(REFERENCE_DT);
+/// }
+/// assert!(world.particle_count() > 1);
+/// ```
+pub trait Effect {
+    /// Embers in a burst from a rocket at the reference launch speed; a
+    /// faster one throws out more, later stages fewer.
+    fn particle_count(&self) -> usize;
+
+    /// The speed ember `i` of `n` leaves at, in cells per second, before
+    /// the burst is scaled to the rocket. `offset` is a random angle to turn
+    /// the whole burst by.
+    fn velocity(&self, i: usize, n: usize, offset: f64, rng: &mut StdRng) -> Vector;
+
+    /// A push on each ember every step, on top of gravity and the wind, in
+    /// cells per second squared.
+    fn force(&self, _age: f64, _vel: Vector) -> Vector {
+        Vector::zero()
+    }
+
+    /// A push on each ember every step that goes by where it is, `offset`
+    /// from the middle of its burst, like a gravity well left behind there.
+    fn field(&self, _age: f64, _offset: Vector) -> Vector {
+        Vector::zero()
+    }
+
+    /// The color of an ember of hue `hue` that is `age` seconds into a life
+    /// of `lifetime`; white-hot, the hue, then cooling to gray by default.
+    fn color(&self, hue: Color, age: f64, lifetime: f64) -> Color {
+        fade(hue, age, lifetime)
+    }
+
+    /// How long an ember burns, in seconds.
+    fn lifetime(&self, rng: &mut StdRng) -> f64 {
+        rng.gen_range(EMBER_LIFETIME[0]..EMBER_LIFETIME[1])
+    }
+
+    /// Fraction of an ember's speed left after one second.
+    fn drag(&self) -> f64 {
+        DRAG
+    }
+
+    /// Seconds before burning out that an ember starts to glitter,
+    /// blinking brighter and dimmer at random; 0 for none.
+    fn sparkle(&self) -> f64 {
+        SPARKLE
+    }
+
+    /// Points in the tail of each ember.
+    fn trail_len(&self) -> usize {
+        2
+    }
+
+    /// Stages it bursts in, however few the world asks for.
+    fn min_depth(&self) -> u8 {
+        1
+    }
+}
+
+/// An effect registered with a `World`, which `BurstType::Custom` launches.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct EffectId {
+    pub(super) index: usize,
+    pub(super) name: &'static str,
+}
@@ -0,0 +1,89 @@
+use super::{burst::BurstType, particle::Particle, vector::Vector};
+use rand::{rngs::StdRng, Rng};
+use ratatui::style::Color;
+
+pub(super) const MAX_PARTICLES_COLOR: usize = 1000;
+
+// share of its group's slots fountains and exhaust leave free, and the
+// upward speed of a fountain's sparks
+const FOUNTAIN_HEADROOM: f64 = 0.2;
+const FOUNTAIN_SPEED: [f64; 2] = [20.0, 35.0];
+// how long fountain sparks burn, in seconds
+const SPARK_LIFETIME: [f64; 2] = [0.8, 1.4];
+
+#[derive(Clone, Debug)]
+pub(super) struct ParticleGroup {
+    // only ever the live ones; spent particles are retired after every step
+    pub(super) particles: Vec<Particle>,
+    // most particles the group holds at once
+    pub(super) capacity: usize,
+    pub(super) color: Color,
+}
+
+impl ParticleGroup {
+    pub(super) fn new(color: Color, capacity: usize) -> ParticleGroup {
+        ParticleGroup {
+            particles: Vec::new(),
+            capacity,
+            color,
+        }
+    }
+
+    pub(super) fn live(&self) -> usize {
+        self.particles.len()
+    }
+
+    /// Adds `particle` unless the group is full, in which case it never
+    /// existed.
+    pub(super) fn spawn(&mut self, particle: Particle) -> Option<&mut Particle> {
+        if self.particles.len() >= self.capacity {
+            return None;
+        }
+        self.particles.push(particle);
+        self.particles.last_mut()
+    }
+
+    /// How many more sparks fit while leaving room for rockets and bursts.
+    pub(super) fn spare(&self) -> usize {
+        let headroom = (self.capacity as f64 * FOUNTAIN_HEADROOM) as usize;
+        (self.capacity - headroom).saturating_sub(self.live())
+    }
+
+    /// Drops every particle that burnt out, exploded or left the screen.
+    pub(super) fn retire(&mut self) {
+        self.particles.retain(Particle::is_alive);
+    }
+}
+
+/// A steady spray of sparks from a fixed point, with no explosion.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FountainEmitter {
+    pub pos: Vector,
+    /// sparks per second
+    pub rate: f64,
+    /// largest sideways speed of a spark
+    pub spread: f64,
+    pub color_index: usize,
+}
+
+impl FountainEmitter {
+    // the sparks it sprayed this step
+    pub(super) fn emit(&self, pgroup: &mut ParticleGroup, dt: f64, rng: &mut StdRng) -> usize {
+        // back off before the group fills up so rockets still have room
+        let budget = pgroup.spare();
+        // rounded up or down at random, so any dt averages out to `rate`
+        let sparks = (self.rate * dt + rng.gen_range(0.0..1.0)) as usize;
+        let sparks = sparks.min(budget);
+        for _ in 0..sparks {
+            let vel = Vector {
+                x: rng.gen_range(-self.spread..=self.spread),
+                y: rng.gen_range(FOUNTAIN_SPEED[0]..FOUNTAIN_SPEED[1]),
+            };
+            let lifetime = rng.gen_range(SPARK_LIFETIME[0]..SPARK_LIFETIME[1]);
+            if let Some(spark) = pgroup.spawn(Particle::new(1, self.pos, vel, BurstType::Peony)) {
+                spark.lifetime = lifetime;
+            }
+        }
+        sparks
+    }
+}
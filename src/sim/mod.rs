@@ -0,0 +1,21 @@
+mod burst;
+mod color;
+mod effect;
+mod glyph;
+mod group;
+mod particle;
+mod smoke;
+mod stats;
+mod vector;
+mod well;
+mod world;
+
+pub use burst::BurstType;
+pub use color::DEFAULT_PALETTE;
+pub use effect::{Effect, EffectId};
+pub use group::FountainEmitter;
+pub use particle::APEX_SPEED;
+pub use stats::Stats;
+pub use vector::Vector;
+pub use well::Well;
+pub use world::{Event, Ground, Kind, World, MAX_DEPTH, REFERENCE_DT};
@@ -0,0 +1,284 @@
+use super::{
+    burst::BurstType,
+    color::{glint, FADE_AGES},
+    effect::Effect,
+    vector::Vector,
+};
+use rand::{rngs::StdRng, Rng};
+use ratatui::style::Color;
+
+// share of an ember's vertical and horizontal speed kept on every bounce
+const BOUNCE_RESTITUTION: f64 = 0.4;
+const BOUNCE_FRICTION: f64 = 0.8;
+const BOUNCE_REST_SPEED: f64 = 2.0;
+// share of its speed a sliding ember keeps every second, and the most
+// seconds it goes on burning once it's down
+const SLIDE_FRICTION: f64 = 0.05;
+const SLIDE_TIME: f64 = 0.6;
+
+// how often a rocket's head sputters, and a glittering ember blinks
+const CRACKLE_INTERVAL: f64 = 0.01;
+const SPARKLE_INTERVAL: f64 = 0.04;
+// how much dimmer and brighter a glittering ember blinks, and the odds it
+// blinks out altogether
+const GLINT: [f64; 2] = [0.2, 1.8];
+const GLINT_OFF: f64 = 0.2;
+
+/// How fast a rocket is falling when it bursts, just past its apex, in
+/// cells per second; a velocity, so it holds for any dt.
+pub const APEX_SPEED: f64 = 5.0;
+
+// seconds a charged ember burns before it bursts
+const SECONDARY_FUSE: f64 = 0.6;
+
+// seconds a rocket climbs before it bursts anyway, for when gravity is too
+// weak to ever slow it to APEX_SPEED
+const ROCKET_FUSE: f64 = 6.0;
+
+// points in a rocket's tail, the longest there is, and the seconds between
+// two of them
+const TRAIL_LEN: usize = 6;
+const TRAIL_INTERVAL: f64 = 0.02;
+// how much older than the particle each point of its tail looks
+const TRAIL_AGING: f64 = 0.3;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(super) enum Flicker {
+    Steady,
+    Bright,
+    // its color times that brightness
+    Glint(f64),
+    Off,
+}
+
+impl Flicker {
+    fn random(rng: &mut StdRng) -> Flicker {
+        match rng.gen_bool(GLINT_OFF) {
+            true => Flicker::Off,
+            false => Flicker::Glint(rng.gen_range(GLINT[0]..GLINT[1])),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(super) enum State {
+    // a rocket on its way up
+    Ascending,
+    // an ember or a spark; a charged one bursts again once its fuse is out
+    Spark { charged: bool },
+    // at the apex or out of fuse, bursting after this step
+    Exploding,
+    // burnt out or out of sight, gone after this step
+    Spent,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub(super) struct Particle {
+    pub(super) pos: Vector,
+    pub(super) vel: Vector,
+    pub(super) acc: Vector,
+    pub(super) state: State,
+    // 0 is the rocket, 1 its embers, 2 the embers of a secondary burst...
+    pub(super) generation: u8,
+    pub(super) age: f64,
+    // seconds until it burns out; the last of it glitters
+    pub(super) lifetime: f64,
+    pub(super) flicker: Flicker,
+    pub(super) launch_speed: f64,
+    pub(super) burst: BurstType,
+    // where it was thrown out from, the middle of its burst for an ember
+    pub(super) origin: Vector,
+    // a rocket's burst size, times its type's usual number of embers, and
+    // the speed it is falling at when it bursts
+    pub(super) shell: f64,
+    pub(super) apex: f64,
+    // where it was, most recent first; only the first trail_len count
+    pub(super) trail: [Vector; TRAIL_LEN],
+    pub(super) trail_len: usize,
+    since_trail: f64,
+}
+
+impl Particle {
+    pub(super) fn new(generation: u8, ipos: Vector, ivel: Vector, burst: BurstType) -> Particle {
+        Particle {
+            pos: ipos,
+            vel: ivel,
+            acc: Vector { x: 0.0, y: 0.0 },
+            state: match generation {
+                0 => State::Ascending,
+                _ => State::Spark { charged: false },
+            },
+            generation,
+            age: 0.0,
+            lifetime: f64::INFINITY,
+            flicker: Flicker::Steady,
+            launch_speed: (ivel.x.powi(2) + ivel.y.powi(2)).sqrt(),
+            burst,
+            origin: ipos,
+            shell: 1.0,
+            apex: APEX_SPEED,
+            trail: [ipos; TRAIL_LEN],
+            trail_len: 0,
+            since_trail: 0.0,
+        }
+    }
+
+    /// The tail behind it, each point dimmer than the one before.
+    pub(super) fn trail<'a>(
+        &'a self,
+        hue: Color,
+        effect: &'a dyn Effect,
+    ) -> impl Iterator<Item = (f64, f64, Color)> + 'a {
+        let heat = self.heat();
+        self.trail[..self.trail_len]
+            .iter()
+            .enumerate()
+            .map(move |(k, pos)| {
+                let age = heat + TRAIL_AGING * (k + 1) as f64;
+                (pos.x, pos.y, effect.color(hue, age, self.lifetime))
+            })
+    }
+
+    fn leave_trail(&mut self, dt: f64, effect: &dyn Effect) {
+        self.since_trail += dt;
+        if self.since_trail < TRAIL_INTERVAL {
+            return;
+        }
+        self.since_trail = 0.0;
+        let max = match self.generation {
+            0 => TRAIL_LEN,
+            _ => effect.trail_len().min(TRAIL_LEN),
+        };
+        self.trail.copy_within(0..TRAIL_LEN - 1, 1);
+        self.trail[0] = self.pos;
+        self.trail_len = (self.trail_len + 1).min(max);
+    }
+
+    // without `flashing` embers skip their white-hot start
+    pub(super) fn color(&self, hue: Color, effect: &dyn Effect, flashing: bool) -> Option<Color> {
+        let heat = match flashing {
+            true => self.heat(),
+            false => self.heat().max(FADE_AGES[0]),
+        };
+        match self.flicker {
+            Flicker::Steady => Some(effect.color(hue, heat, self.lifetime)),
+            Flicker::Bright => Some(Color::White),
+            Flicker::Glint(brightness) => {
+                Some(glint(effect.color(hue, heat, self.lifetime), brightness))
+            }
+            Flicker::Off => None,
+        }
+    }
+
+    pub(super) fn crackle(&mut self, dt: f64, effect: &dyn Effect, rng: &mut StdRng) {
+        // only when the last step crossed into a new interval
+        let crossed =
+            |interval: f64| (self.age / interval).floor() != ((self.age - dt) / interval).floor();
+        match self.state {
+            // a rocket's head sputters, but never goes dark
+            State::Ascending if crossed(CRACKLE_INTERVAL) => {
+                self.flicker = match rng.gen_range(0..3) {
+                    0 => Flicker::Bright,
+                    _ => Flicker::Steady,
+                }
+            }
+            State::Spark { .. }
+                if self.age >= self.lifetime - effect.sparkle() && crossed(SPARKLE_INTERVAL) =>
+            {
+                self.flicker = Flicker::random(rng);
+            }
+            _ => {}
+        }
+    }
+
+    pub(super) fn is_alive(&self) -> bool {
+        matches!(self.state, State::Ascending | State::Spark { .. })
+    }
+
+    /// Makes an ember burst again once its fuse is out.
+    pub(super) fn charge(&mut self) {
+        if let State::Spark { charged } = &mut self.state {
+            *charged = true;
+        }
+    }
+
+    // the age its color goes by; rockets stay their hue all the way up
+    fn heat(&self) -> f64 {
+        match self.generation {
+            0 => FADE_AGES[0],
+            _ => self.age,
+        }
+    }
+
+    pub(super) fn apply_force(&mut self, force: Vector) {
+        self.acc = self.acc + force;
+    }
+
+    pub(super) fn bounce(&mut self, floor: f64) {
+        if !matches!(self.state, State::Spark { .. }) || self.pos.y > floor || self.vel.y >= 0.0 {
+            return;
+        }
+        self.pos.y = floor;
+        self.vel.y = -self.vel.y * BOUNCE_RESTITUTION;
+        self.vel.x *= BOUNCE_FRICTION;
+        if self.vel.y < BOUNCE_REST_SPEED {
+            // too slow to hop again, so it burns out on the ground
+            self.state = State::Spent;
+        }
+    }
+
+    pub(super) fn slide(&mut self, floor: f64, dt: f64) {
+        if !matches!(self.state, State::Spark { .. }) || self.pos.y > floor {
+            return;
+        }
+        self.pos.y = floor;
+        self.vel.y = self.vel.y.max(0.0);
+        self.vel.x *= SLIDE_FRICTION.powf(dt);
+        // the first touch sets how long it has left
+        self.lifetime = self.lifetime.min(self.age + SLIDE_TIME);
+    }
+
+    // back in from the other side of a screen `width` cells across, taking
+    // none of its tail across with it
+    pub(super) fn wrap(&mut self, width: f64) {
+        let half = width / 2.0;
+        if (-half..half).contains(&self.pos.x) {
+            return;
+        }
+        self.pos.x = (self.pos.x + half).rem_euclid(width) - half;
+        self.trail_len = 0;
+    }
+
+    // without a bounce, embers that drop out of sight are gone for good
+    pub(super) fn fall_through(&mut self, floor: f64) {
+        if matches!(self.state, State::Spark { .. }) && self.pos.y < floor - 1.0 {
+            self.state = State::Spent;
+        }
+    }
+
+    // `drag` multiplies the effect's own
+    pub(super) fn update(&mut self, dt: f64, effect: &dyn Effect, drag: f64) {
+        let fused = match self.state {
+            State::Ascending => self.vel.y <= -self.apex || self.age >= ROCKET_FUSE,
+            // burnt out, with no explosion even if it was charged
+            State::Spark { .. } if self.age >= self.lifetime => {
+                self.state = State::Spent;
+                return;
+            }
+            State::Spark { charged } => charged && self.age >= SECONDARY_FUSE,
+            State::Exploding | State::Spent => return,
+        };
+        if fused {
+            self.state = State::Exploding;
+            return;
+        }
+        self.leave_trail(dt, effect);
+        self.vel = self.vel + self.acc * dt;
+        self.pos = self.pos + self.vel * dt;
+        self.acc = self.acc * 0.0;
+        self.age += dt;
+        if self.generation > 0 {
+            self.vel = self.vel * effect.drag().powf(dt * drag);
+        }
+    }
+}
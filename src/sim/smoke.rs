@@ -0,0 +1,68 @@
+use super::vector::Vector;
+use rand::{rngs::StdRng, Rng};
+use ratatui::style::Color;
+
+// puffs a burst leaves behind, and the most that hang in the air at once
+pub(super) const PUFFS_PER_BURST: usize = 10;
+pub(super) const MAX_SMOKE: usize = 800;
+
+// how fast a fresh puff drifts off the burst, how hard it rises, how much it
+// wanders and how quickly the air slows it down (share of speed kept after
+// a second)
+const PUFF_SPEED: f64 = 4.0;
+const BUOYANCY: f64 = 2.0;
+const DIFFUSION: f64 = 6.0;
+const SMOKE_DRAG: f64 = 0.3;
+// seconds a puff takes to clear
+const SMOKE_LIFETIME: [f64; 2] = [3.0, 5.0];
+// how gray a fresh puff is; it fades to nothing from there
+const SMOKE_GRAY: f64 = 80.0;
+
+/// A puff of smoke: no gravity, rising slowly, wandering about at random and
+/// carried along by the wind.
+#[derive(Copy, Clone, Debug)]
+pub(super) struct Smoke {
+    pos: Vector,
+    vel: Vector,
+    age: f64,
+    lifetime: f64,
+}
+
+impl Smoke {
+    pub(super) fn puff(pos: Vector, rng: &mut StdRng) -> Smoke {
+        Smoke {
+            pos,
+            vel: Vector {
+                x: rng.gen_range(-PUFF_SPEED..PUFF_SPEED),
+                y: rng.gen_range(-PUFF_SPEED..PUFF_SPEED) * 0.5,
+            },
+            age: 0.0,
+            lifetime: rng.gen_range(SMOKE_LIFETIME[0]..SMOKE_LIFETIME[1]),
+        }
+    }
+
+    pub(super) fn is_gone(&self) -> bool {
+        self.age >= self.lifetime
+    }
+
+    pub(super) fn update(&mut self, dt: f64, wind: f64, rng: &mut StdRng) {
+        // knocks that add up the same however the time is cut into steps
+        let jitter = Vector {
+            x: rng.gen_range(-1.0..1.0),
+            y: rng.gen_range(-1.0..1.0),
+        } * (DIFFUSION * dt.sqrt());
+        self.vel = self.vel * SMOKE_DRAG.powf(dt)
+            + Vector {
+                x: wind,
+                y: BUOYANCY,
+            } * dt
+            + jitter;
+        self.pos = self.pos + self.vel * dt;
+        self.age += dt;
+    }
+
+    pub(super) fn draw(&self) -> (f64, f64, Color) {
+        let gray = (SMOKE_GRAY * (1.0 - self.age / self.lifetime).max(0.0)) as u8;
+        (self.pos.x, self.pos.y, Color::Rgb(gray, gray, gray))
+    }
+}
@@ -0,0 +1,13 @@
+/// Running totals of what a world got up to since it was made, for a
+/// summary of the show.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct Stats {
+    /// Rockets that went up, leaving out those a full color had no room for.
+    pub launches: u64,
+    /// Bursts of every stage, rockets and charged embers alike.
+    pub bursts: u64,
+    /// Embers thrown out by the bursts and sprayed by fountains.
+    pub sparks: u64,
+    /// The most particles alive at the end of any step.
+    pub peak_particles: usize,
+}
@@ -0,0 +1,76 @@
+use rand::{rngs::StdRng, Rng};
+use std::{
+    f64::consts::PI,
+    ops::{Add, Mul, Sub},
+};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Vector {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Vector {
+    pub fn zero() -> Vector {
+        Vector { x: 0.0, y: 0.0 }
+    }
+}
+
+impl Add for Vector {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            x: self.x + other.x,
+            y: self.y + other.y,
+        }
+    }
+}
+
+impl Sub for Vector {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self {
+            x: self.x - other.x,
+            y: self.y - other.y,
+        }
+    }
+}
+
+impl Mul<f64> for Vector {
+    type Output = Self;
+
+    fn mul(self, scalar: f64) -> Self {
+        Self {
+            x: self.x * scalar,
+            y: self.y * scalar,
+        }
+    }
+}
+
+// sampling an angle is always unit length and uniform over directions,
+// unlike normalising a random point in the square
+pub(super) fn random_unit_vector(rng: &mut StdRng) -> Vector {
+    let angle: f64 = rng.gen_range(0.0..2.0 * PI);
+    Vector {
+        x: angle.cos(),
+        y: angle.sin(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn random_unit_vector_is_unit_length() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..10_000 {
+            let v = random_unit_vector(&mut rng);
+            let abs = (v.x.powi(2) + v.y.powi(2)).sqrt();
+            assert!((abs - 1.0).abs() < 1e-9, "{:?} has length {}", v, abs);
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use super::vector::Vector;
+
+/// An invisible point pulling the sparks around it in, or pushing them away
+/// with a negative `strength`, and swirling them around it with `spin`,
+/// anticlockwise when positive. Both are in cells per second squared, at
+/// full strength right by the point and fading out to nothing at `radius`
+/// cells from it.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Well {
+    pub pos: Vector,
+    pub strength: f64,
+    pub spin: f64,
+    pub radius: f64,
+}
+
+impl Well {
+    /// The push on a spark at `pos`.
+    pub fn force(&self, pos: Vector) -> Vector {
+        let delta = self.pos - pos;
+        let dist = (delta.x.powi(2) + delta.y.powi(2)).sqrt();
+        if dist < 1e-6 || dist >= self.radius {
+            return Vector::zero();
+        }
+        let inward = delta * (1.0 / dist);
+        let around = Vector {
+            x: inward.y,
+            y: -inward.x,
+        };
+        (inward * self.strength + around * self.spin) * (1.0 - dist / self.radius)
+    }
+}
@@ -0,0 +1,1268 @@
+use super::{
+    burst::{BurstType, BURSTS, MESSAGE_DRAG},
+    color::DEFAULT_PALETTE,
+    effect::{Effect, EffectId},
+    glyph::{rasterize, GLYPH_HEIGHT, GLYPH_WIDTH},
+    group::{FountainEmitter, ParticleGroup, MAX_PARTICLES_COLOR},
+    particle::{Particle, State, APEX_SPEED},
+    smoke::{Smoke, MAX_SMOKE, PUFFS_PER_BURST},
+    stats::Stats,
+    vector::Vector,
+    well::Well,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use ratatui::style::Color;
+use std::f64::consts::PI;
+use tracing::{debug, debug_span};
+
+// Units are canvas cells and seconds. The constants were tuned back when
+// the simulation advanced a fixed step of REFERENCE_DT per tick, so a
+// 10ms tick still looks exactly like it always did.
+pub const REFERENCE_DT: f64 = 0.01;
+
+// how long a gust lasts, in seconds, and how far it takes the wind from its
+// set strength, as a share of it
+const GUST_TIME: f64 = 3.0;
+const GUST_SHARE: f64 = 0.5;
+
+// sparks a rising rocket sputters out per second, how long they burn and
+// how much of the rocket's speed they are thrown back with
+const EXHAUST_RATE: f64 = 60.0;
+const EXHAUST_LIFETIME: [f64; 2] = [0.1, 0.3];
+const EXHAUST_KICK: f64 = 0.15;
+
+// mixed into the seed of the smoke's dice, so they differ from the sparks'
+const SMOKE_SEED: u64 = 0x5307e;
+
+const ATTRACTOR_STRENGTH: f64 = 4000.0;
+const ATTRACTOR_SOFTENING: f64 = 25.0;
+
+// a rocket launched at this speed gets a burst of its type's usual size;
+// faster ones get bigger, wider bursts, slower ones smaller, within limits
+const BURST_REFERENCE_SPEED: f64 = 40.0;
+const MIN_BURST: usize = 6;
+const MAX_BURST: usize = 64;
+const BURST_SPREAD: [f64; 2] = [0.5, 2.0];
+// every rocket's shell holds between these times its type's usual number of
+// embers, and bursts going up to this much faster or slower than just past
+// its apex, so small and big ones go off at different heights
+const SHELL_SIZES: [f64; 2] = [0.8, 3.0];
+const APEX_FUZZ: f64 = 10.0;
+
+// embers per burst that carry a charge for the next stage; at MAX_DEPTH a
+// single rocket spawns at most 80 + 3 * 40 + 9 * 20 = 380 particles, so a
+// cascade stays well inside MAX_PARTICLES_COLOR
+const CHARGED_PER_BURST: usize = 3;
+pub const MAX_DEPTH: u8 = 3;
+
+/// What embers do once they come down to the floor.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Ground {
+    /// Drop out of sight and are gone.
+    #[default]
+    FallThrough,
+    /// Hop back up, a little lower every time, until they come to rest.
+    Bounce,
+    /// Skid along it, slowing down, and burn out soon after.
+    Slide,
+}
+
+impl Ground {
+    pub const ALL: [Ground; 3] = [Ground::FallThrough, Ground::Bounce, Ground::Slide];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Ground::FallThrough => "through",
+            Ground::Bounce => "bounce",
+            Ground::Slide => "slide",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Ground> {
+        Ground::ALL.into_iter().find(|g| g.name() == name)
+    }
+}
+
+/// What a point `particles_by_kind` gives is part of.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Kind {
+    /// The head of a rocket on its way up.
+    Rocket,
+    /// An ember of a burst, or a spark from a rocket or a fountain.
+    Spark,
+    /// A point of the tail behind either.
+    Trail,
+}
+
+/// Something that happened during a step, for whoever wants to react to it
+/// (with a sound, say). Only kept once `listen` has been called.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Event {
+    /// A rocket left `pos` at `vel`, to burst into `burst`.
+    Launch {
+        color_index: usize,
+        pos: Vector,
+        vel: Vector,
+        burst: BurstType,
+    },
+    /// A rocket or charged ember burst into `embers` embers; `generation` is
+    /// 0 for rockets.
+    Burst {
+        pos: Vector,
+        burst: BurstType,
+        generation: u8,
+        embers: usize,
+    },
+}
+
+/// The fireworks physics, independent of any terminal or renderer.
+pub struct World {
+    particle_groups: Vec<ParticleGroup>,
+    gravity: Vector,
+    // how thick the air is, multiplying every burst's own drag
+    drag: f64,
+    // sideways push, positive to the right, and the share of it a gust
+    // adds on top for now
+    wind: f64,
+    gust: f64,
+    turbulence: f64,
+    rng: StdRng,
+    // None picks a random burst for every launch
+    burst: Option<BurstType>,
+    // number of explosion stages a rocket goes through
+    max_depth: u8,
+    floor: Option<f64>,
+    ground: Ground,
+    // as wide as the screen, for particles leaving one side to come back
+    // on the other; None lets them fly off
+    wrap: Option<f64>,
+    attractor: Option<Vector>,
+    wells: Vec<Well>,
+    fountains: Vec<FountainEmitter>,
+    burst_scale: f64,
+    // embers in a burst, in place of each type's own count
+    burst_particles: Option<usize>,
+    shell_sizes: [f64; 2],
+    apex_fuzz: f64,
+    // multiplies the number of embers in every burst
+    density: f64,
+    trails: bool,
+    // rocket heads sputtering, embers bursting white-hot and glittering
+    flashing: bool,
+    // where each ember of a message burst ends up, around the burst
+    message: Vec<Vector>,
+    // None until somebody listens
+    events: Option<Vec<Event>>,
+    // what `BurstType::Custom` looks up, in the order they were registered
+    effects: Vec<(EffectId, Box<dyn Effect + Send>)>,
+    // whether bursts leave smoke, and the puffs still hanging about; the
+    // smoke has dice of its own so it leaves the sparks as they were
+    smoke: bool,
+    puffs: Vec<Smoke>,
+    smoke_rng: StdRng,
+    stats: Stats,
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl World {
+    pub fn new() -> World {
+        World::with_palette(&DEFAULT_PALETTE)
+    }
+
+    /// A simulation with one particle group per color of `palette`.
+    pub fn with_palette(palette: &[Color]) -> World {
+        World {
+            particle_groups: palette
+                .iter()
+                .map(|c| ParticleGroup::new(*c, MAX_PARTICLES_COLOR))
+                .collect(),
+            gravity: Vector { x: 0.0, y: -40.0 },
+            drag: 1.0,
+            wind: 0.0,
+            gust: 0.0,
+            turbulence: 0.0,
+            rng: StdRng::from_entropy(),
+            burst: None,
+            max_depth: 1,
+            floor: None,
+            ground: Ground::FallThrough,
+            wrap: None,
+            attractor: None,
+            wells: Vec::new(),
+            fountains: Vec::new(),
+            burst_scale: 1.0,
+            burst_particles: None,
+            shell_sizes: SHELL_SIZES,
+            apex_fuzz: APEX_FUZZ,
+            density: 1.0,
+            trails: true,
+            flashing: true,
+            message: Vec::new(),
+            events: None,
+            effects: Vec::new(),
+            smoke: false,
+            puffs: Vec::new(),
+            smoke_rng: StdRng::from_entropy(),
+            stats: Stats::default(),
+        }
+    }
+
+    /// Makes every explosion from now on reproducible.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.set_rng(StdRng::seed_from_u64(seed));
+    }
+
+    /// Draws every explosion from now on from `rng`.
+    pub fn set_rng(&mut self, rng: StdRng) {
+        self.smoke_rng = StdRng::seed_from_u64(rng.clone().gen::<u64>() ^ SMOKE_SEED);
+        self.rng = rng;
+    }
+
+    pub fn palette(&self) -> Vec<Color> {
+        self.particle_groups.iter().map(|g| g.color).collect()
+    }
+
+    /// Repaints the particle groups in the colors of `palette`, in order,
+    /// sparks already flying included. Groups past its end go out with
+    /// whatever was still alive in them, and new ones get as much room as
+    /// the others.
+    pub fn set_palette(&mut self, palette: &[Color]) {
+        let capacity = self.max_particles();
+        self.particle_groups.truncate(palette.len());
+        for (i, color) in palette.iter().enumerate() {
+            match self.particle_groups.get_mut(i) {
+                Some(group) => group.color = *color,
+                None => self
+                    .particle_groups
+                    .push(ParticleGroup::new(*color, capacity)),
+            }
+        }
+    }
+
+    /// Fires a rocket in `palette()[color_index]`, exploding at its apex.
+    /// Like one whose color is full, a rocket in a color past the end of
+    /// the palette doesn't go up.
+    pub fn launch(&mut self, color_index: usize, pos: Vector, vel: Vector) {
+        let burst = self.next_burst();
+        self.launch_burst(color_index, pos, vel, burst);
+    }
+
+    /// Like `launch`, but bursting into `burst` whatever `cycle_burst` picked.
+    pub fn launch_burst(&mut self, color_index: usize, pos: Vector, vel: Vector, burst: BurstType) {
+        let _launch = debug_span!("launch", color = color_index).entered();
+        let [smallest, biggest] = self.shell_sizes;
+        let mut rocket = Particle::new(0, pos, vel, burst);
+        rocket.shell = self.rng.gen_range(smallest..=biggest);
+        rocket.apex = APEX_SPEED + self.rng.gen_range(-self.apex_fuzz..=self.apex_fuzz);
+        let group = self.particle_groups.get_mut(color_index);
+        let launched = group.and_then(|group| group.spawn(rocket)).is_some();
+        self.stats.launches += u64::from(launched);
+        match launched {
+            true => debug!("launched a {}", burst.name()),
+            false => debug!(
+                "the color is full or missing, a {} didn't go up",
+                burst.name()
+            ),
+        }
+        if let (true, Some(events)) = (launched, self.events.as_mut()) {
+            events.push(Event::Launch {
+                color_index,
+                pos,
+                vel,
+                burst,
+            });
+        }
+    }
+
+    /// Starts keeping `Event`s for `take_events`.
+    pub fn listen(&mut self) {
+        self.events.get_or_insert_with(Vec::new);
+    }
+
+    /// What happened since the last call.
+    pub fn take_events(&mut self) -> Vec<Event> {
+        self.events.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    pub fn gravity(&self) -> Vector {
+        self.gravity
+    }
+
+    /// Sets how hard particles are pulled down, in cells per second squared.
+    pub fn set_gravity(&mut self, strength: f64) {
+        self.gravity = Vector {
+            x: 0.0,
+            y: -strength,
+        };
+    }
+
+    pub fn drag(&self) -> f64 {
+        self.drag
+    }
+
+    /// Multiplies how quickly the air slows embers down, 1.0 leaving every
+    /// burst with its own drag and 0 taking it away.
+    pub fn set_drag(&mut self, scale: f64) {
+        self.drag = scale.max(0.0);
+    }
+
+    /// The sideways push the wind gusts around, in cells per second
+    /// squared; positive blows to the right.
+    pub fn wind(&self) -> f64 {
+        self.wind
+    }
+
+    /// The sideways push right now, gust included.
+    pub fn wind_now(&self) -> f64 {
+        self.wind * (1.0 + self.gust)
+    }
+
+    pub fn set_wind(&mut self, strength: f64) {
+        self.wind = strength;
+    }
+
+    pub fn turbulence(&self) -> f64 {
+        self.turbulence
+    }
+
+    /// Sets how hard embers get knocked about at random, 0 for still air.
+    pub fn set_turbulence(&mut self, strength: f64) {
+        self.turbulence = strength.max(0.0);
+    }
+
+    /// Caps the number of particles alive at once in each color; bursts
+    /// that don't fit lose their extra embers.
+    pub fn set_max_particles(&mut self, n: usize) {
+        for group in self.particle_groups.iter_mut() {
+            group.capacity = n;
+            group.particles.truncate(n);
+        }
+    }
+
+    pub fn max_particles(&self) -> usize {
+        self.particle_groups.first().map_or(0, |g| g.capacity)
+    }
+
+    /// Sets the height of the bottom of the screen, where embers bounce.
+    pub fn set_floor(&mut self, y: f64) {
+        self.floor = Some(y);
+    }
+
+    pub fn ground(&self) -> Ground {
+        self.ground
+    }
+
+    pub fn set_ground(&mut self, ground: Ground) {
+        self.ground = ground;
+    }
+
+    /// Goes to the next of `Ground::ALL`.
+    pub fn cycle_ground(&mut self) {
+        let i = Ground::ALL
+            .iter()
+            .position(|g| *g == self.ground)
+            .unwrap_or(0);
+        self.ground = Ground::ALL[(i + 1) % Ground::ALL.len()];
+    }
+
+    /// Makes particles leaving the screen at one side come back in at the
+    /// other, the screen being `width` cells across and centered on 0, or
+    /// lets them fly off with `None`.
+    pub fn set_wrap(&mut self, width: Option<f64>) {
+        self.wrap = width.filter(|w| *w > 0.0);
+    }
+
+    /// Shows or hides the tails particles leave behind them.
+    pub fn toggle_trails(&mut self) {
+        self.trails = !self.trails;
+    }
+
+    /// Lets rocket heads sputter and embers burst white-hot and glitter
+    /// before going out, or keeps every spark steady.
+    pub fn set_flashing(&mut self, flashing: bool) {
+        self.flashing = flashing;
+    }
+
+    /// Makes bursts leave smoke behind them, or stops them; puffs already
+    /// in the air clear on their own.
+    pub fn toggle_smoke(&mut self) {
+        self.smoke = !self.smoke;
+    }
+
+    /// Pulls every particle towards `pos` until cleared with `None`.
+    pub fn set_attractor(&mut self, pos: Option<Vector>) {
+        self.attractor = pos;
+    }
+
+    /// Multiplies the size of every burst, 1.0 being the default.
+    pub fn set_burst_scale(&mut self, scale: f64) {
+        self.burst_scale = scale;
+    }
+
+    /// Embers every burst gets at the reference launch speed, if not its
+    /// type's own number.
+    pub fn burst_particles(&self) -> Option<usize> {
+        self.burst_particles
+    }
+
+    /// Gives every burst `n` embers at the reference launch speed, whatever
+    /// its type.
+    pub fn set_burst_particles(&mut self, n: usize) {
+        self.burst_particles = Some(n);
+    }
+
+    /// Sizes every rocket's shell at random between `min` and `max` times
+    /// its type's usual number of embers; 1 and 1 makes them all the same.
+    pub fn set_shell_sizes(&mut self, min: f64, max: f64) {
+        let min = min.max(0.0);
+        self.shell_sizes = [min, max.max(min)];
+    }
+
+    /// Multiplies the number of embers in every burst, 1.0 being the
+    /// default; unlike `set_burst_scale` it leaves how far they fly.
+    pub fn set_density(&mut self, density: f64) {
+        self.density = density.max(0.0);
+    }
+
+    /// How much faster or slower than just past its apex a rocket may be
+    /// falling when it bursts, in cells per second; 0 bursts every one at
+    /// the same point of its climb.
+    pub fn set_apex_fuzz(&mut self, speed: f64) {
+        self.apex_fuzz = speed.max(0.0);
+    }
+
+    /// Makes `BurstType::Message` bursts spell out `text`, as big as fits in
+    /// `width` by `height` cells. Without a message they burst like a peony.
+    pub fn set_message(&mut self, text: &str, width: f64, height: f64) {
+        let columns = (text.chars().count() * (GLYPH_WIDTH + 1)) as f64;
+        // cells are about twice as tall as they are wide, so a pixel takes
+        // twice as many of them across as it does down
+        let pixel = (height / GLYPH_HEIGHT as f64).min(width / columns / 2.0);
+        self.message = rasterize(text)
+            .into_iter()
+            .map(|p| Vector {
+                x: p.x * pixel * 2.0,
+                y: p.y * pixel,
+            })
+            .collect();
+    }
+
+    pub fn has_message(&self) -> bool {
+        !self.message.is_empty()
+    }
+
+    /// Leaves a well that pulls or swirls every spark near it, rockets
+    /// going up past it unharmed.
+    pub fn add_well(&mut self, well: Well) {
+        self.wells.push(well);
+    }
+
+    pub fn clear_wells(&mut self) {
+        self.wells.clear();
+    }
+
+    pub fn wells(&self) -> &[Well] {
+        &self.wells
+    }
+
+    pub fn add_fountain(&mut self, fountain: FountainEmitter) {
+        self.fountains.push(fountain);
+    }
+
+    pub fn clear_fountains(&mut self) {
+        self.fountains.clear();
+    }
+
+    pub fn fountains(&self) -> &[FountainEmitter] {
+        &self.fountains
+    }
+
+    /// Sets how many stages a firework explodes in, from 1 to `MAX_DEPTH`.
+    pub fn set_depth(&mut self, depth: u8) {
+        self.max_depth = depth.clamp(1, MAX_DEPTH);
+    }
+
+    /// Cycles the number of explosion stages between 1 and `MAX_DEPTH`.
+    pub fn cycle_depth(&mut self) {
+        self.max_depth = self.max_depth % MAX_DEPTH + 1;
+    }
+
+    /// Adds `effect` to the bursts `cycle_burst` goes through, returning
+    /// what to `launch_burst` it with.
+    pub fn register_effect(
+        &mut self,
+        name: &'static str,
+        effect: impl Effect + Send + 'static,
+    ) -> BurstType {
+        let id = EffectId {
+            index: self.effects.len(),
+            name,
+        };
+        self.effects.push((id, Box::new(effect)));
+        BurstType::Custom(id)
+    }
+
+    /// The built-in or registered burst called `name`.
+    pub fn burst_named(&self, name: &str) -> Option<BurstType> {
+        BurstType::from_name(name).or_else(|| self.shapes().into_iter().find(|b| b.name() == name))
+    }
+
+    // what cycle_burst steps through, the built-in ones first
+    fn shapes(&self) -> Vec<BurstType> {
+        let custom = self.effects.iter().map(|(id, _)| BurstType::Custom(*id));
+        BURSTS.iter().copied().chain(custom).collect()
+    }
+
+    fn next_burst(&mut self) -> BurstType {
+        match self.burst {
+            Some(burst) => burst,
+            None => BurstType::random(&mut self.rng),
+        }
+    }
+
+    /// Cycles the burst type: random, then each of `BURSTS` and the
+    /// registered effects in turn.
+    pub fn cycle_burst(&mut self) {
+        let shapes = self.shapes();
+        self.burst = match self.burst {
+            None => Some(shapes[0]),
+            Some(burst) => {
+                let i = shapes.iter().position(|b| *b == burst).unwrap_or(0);
+                shapes.get(i + 1).copied()
+            }
+        };
+    }
+
+    /// Advances every particle by `dt` seconds.
+    pub fn step(&mut self, dt: f64) {
+        if self.wind != 0.0 && dt > 0.0 {
+            // a random walk pulled back towards no gust, so the wind wanders
+            // around its set strength without drifting off
+            let kick = self.rng.gen_range(-1.0..1.0) * (6.0 * dt / GUST_TIME).sqrt();
+            self.gust += GUST_SHARE * kick - self.gust * dt / GUST_TIME;
+        }
+        let wind = Vector {
+            x: self.wind_now(),
+            y: 0.0,
+        };
+        for puff in self.puffs.iter_mut() {
+            puff.update(dt, wind.x, &mut self.smoke_rng);
+        }
+        self.puffs.retain(|puff| !puff.is_gone());
+        for particle_group in self.particle_groups.iter_mut() {
+            let mut bursts = Vec::new();
+            let mut exhaust = Vec::new();
+            for particle in particle_group.particles.iter_mut() {
+                let burst = particle.burst;
+                let effect = effect(&self.effects, &burst);
+                particle.apply_force(self.gravity + wind);
+                if particle.generation > 0 {
+                    particle.apply_force(effect.force(particle.age, particle.vel));
+                    let offset = particle.pos - particle.origin;
+                    particle.apply_force(effect.field(particle.age, offset));
+                    for well in self.wells.iter() {
+                        particle.apply_force(well.force(particle.pos));
+                    }
+                }
+                if self.turbulence > 0.0 && particle.generation > 0 && dt > 0.0 {
+                    // scaled so the knocks add up the same however the time
+                    // is cut into steps
+                    let jitter = Vector {
+                        x: self.rng.gen_range(-1.0..1.0),
+                        y: self.rng.gen_range(-1.0..1.0),
+                    };
+                    particle.apply_force(jitter * (self.turbulence / dt.sqrt()));
+                }
+                if let Some(attractor) = self.attractor {
+                    particle.apply_force(attraction(particle.pos, attractor));
+                }
+                particle.update(dt, effect, self.drag);
+                if self.flashing {
+                    particle.crackle(dt, effect, &mut self.rng);
+                }
+                if let Some(floor) = self.floor {
+                    match self.ground {
+                        Ground::FallThrough => particle.fall_through(floor),
+                        Ground::Bounce => particle.bounce(floor),
+                        Ground::Slide => particle.slide(floor, dt),
+                    }
+                }
+                if let Some(width) = self.wrap {
+                    particle.wrap(width);
+                }
+                if particle.state == State::Exploding {
+                    bursts.push(*particle);
+                    particle.state = State::Spent;
+                }
+                if particle.state == State::Ascending && self.trails && dt > 0.0 {
+                    // in fits and starts, rounded at random like a fountain
+                    let sparks = (EXHAUST_RATE * dt + self.rng.gen_range(0.0..1.0)) as usize;
+                    for _ in 0..sparks {
+                        let jitter = Vector {
+                            x: self.rng.gen_range(-3.0..3.0),
+                            y: self.rng.gen_range(-3.0..3.0),
+                        };
+                        exhaust.push((particle.pos, particle.vel * -EXHAUST_KICK + jitter));
+                    }
+                }
+            }
+            particle_group.retire();
+            for (pos, vel) in exhaust.into_iter().take(particle_group.spare()) {
+                let lifetime = self.rng.gen_range(EXHAUST_LIFETIME[0]..EXHAUST_LIFETIME[1]);
+                let spark = Particle::new(1, pos, vel, BurstType::Peony);
+                if let Some(spark) = particle_group.spawn(spark) {
+                    spark.lifetime = lifetime;
+                }
+            }
+
+            // embers join after the step, so they first move on the next one
+            for mut p in bursts {
+                if p.burst == BurstType::Message {
+                    if !self.message.is_empty() {
+                        let embers = spell(particle_group, p, &self.message, &mut self.rng);
+                        debug!("the message burst into {} embers", embers);
+                        self.stats.bursts += 1;
+                        self.stats.sparks += embers as u64;
+                        if let Some(events) = self.events.as_mut() {
+                            events.push(Event::Burst {
+                                pos: p.pos,
+                                burst: p.burst,
+                                generation: p.generation,
+                                embers,
+                            });
+                        }
+                        continue;
+                    }
+                    p.burst = BurstType::Peony;
+                }
+                let size = match p.generation {
+                    0 => p.launch_speed / BURST_REFERENCE_SPEED,
+                    _ => 1.0,
+                } * self.burst_scale;
+                // a denser show packs every shell fuller
+                p.shell *= self.density;
+                let effect = effect(&self.effects, &p.burst);
+                let base = self
+                    .burst_particles
+                    .unwrap_or_else(|| effect.particle_count());
+                let embers = explode(
+                    particle_group,
+                    p,
+                    effect,
+                    self.max_depth,
+                    base,
+                    size,
+                    &mut self.rng,
+                );
+                debug!(
+                    "a {} of generation {} burst into {} embers",
+                    p.burst.name(),
+                    p.generation,
+                    embers
+                );
+                self.stats.bursts += 1;
+                self.stats.sparks += embers as u64;
+                if let Some(events) = self.events.as_mut() {
+                    events.push(Event::Burst {
+                        pos: p.pos,
+                        burst: p.burst,
+                        generation: p.generation,
+                        embers,
+                    });
+                }
+                if self.smoke && embers > 0 {
+                    // later stages are smaller and leave less
+                    let puffs = (PUFFS_PER_BURST >> p.generation)
+                        .min(MAX_SMOKE.saturating_sub(self.puffs.len()));
+                    for _ in 0..puffs {
+                        self.puffs.push(Smoke::puff(p.pos, &mut self.smoke_rng));
+                    }
+                }
+            }
+        }
+        for fountain in self.fountains.iter() {
+            if let Some(group) = self.particle_groups.get_mut(fountain.color_index) {
+                self.stats.sparks += fountain.emit(group, dt, &mut self.rng) as u64;
+            }
+        }
+        self.stats.peak_particles = self.stats.peak_particles.max(self.particle_count());
+    }
+
+    /// What it got up to since it was made.
+    pub fn stats(&self) -> Stats {
+        self.stats
+    }
+
+    /// Whether every particle has burnt out.
+    pub fn is_empty(&self) -> bool {
+        self.particle_groups.iter().all(|g| g.particles.is_empty())
+    }
+
+    /// Number of particles still alive, not counting their trails.
+    pub fn particle_count(&self) -> usize {
+        self.particle_groups.iter().map(|g| g.live()).sum()
+    }
+
+    /// How many particles are alive in each color of the palette.
+    pub fn particle_counts(&self) -> impl Iterator<Item = (Color, usize)> + '_ {
+        self.particle_groups.iter().map(|g| (g.color, g.live()))
+    }
+
+    /// Every puff of smoke as `(x, y, color)`, to draw behind the
+    /// `particles`.
+    pub fn smoke(&self) -> impl Iterator<Item = (f64, f64, Color)> + '_ {
+        self.puffs.iter().map(Smoke::draw)
+    }
+
+    /// Every visible particle as `(x, y, color)`, with embers already faded,
+    /// followed by its trail if trails are on.
+    pub fn particles(&self) -> impl Iterator<Item = (f64, f64, Color)> + '_ {
+        self.particles_by_kind()
+            .map(|(x, y, color, _)| (x, y, color))
+    }
+
+    /// Like `particles`, telling what each point is part of, for drawing
+    /// them differently.
+    pub fn particles_by_kind(&self) -> impl Iterator<Item = (f64, f64, Color, Kind)> + '_ {
+        self.particle_groups.iter().flat_map(move |group| {
+            group.particles.iter().flat_map(move |p| {
+                let effect = effect(&self.effects, &p.burst);
+                let kind = match p.generation {
+                    0 => Kind::Rocket,
+                    _ => Kind::Spark,
+                };
+                let head = p
+                    .color(group.color, effect, self.flashing)
+                    .map(|c| (p.pos.x, p.pos.y, c, kind));
+                // a particle flickering out takes its trail with it
+                let shown = self.trails && head.is_some();
+                let trail = p
+                    .trail(group.color, effect)
+                    .filter(move |_| shown)
+                    .map(|(x, y, c)| (x, y, c, Kind::Trail));
+                head.into_iter().chain(trail)
+            })
+        })
+    }
+}
+
+// what a burst looks up to know how to behave: itself, unless it is a
+// registered effect
+fn effect<'a>(
+    effects: &'a [(EffectId, Box<dyn Effect + Send>)],
+    burst: &'a BurstType,
+) -> &'a dyn Effect {
+    match burst {
+        BurstType::Custom(id) => match effects.get(id.index) {
+            Some((_, effect)) => effect.as_ref(),
+            None => burst,
+        },
+        _ => burst,
+    }
+}
+
+fn attraction(pos: Vector, attractor: Vector) -> Vector {
+    let delta = attractor - pos;
+    let dist2 = delta.x.powi(2) + delta.y.powi(2);
+    if dist2 < 1e-12 {
+        return Vector::zero();
+    }
+    // the softening caps the pull at ATTRACTOR_STRENGTH / ATTRACTOR_SOFTENING
+    // so particles right on the cursor aren't flung away
+    delta * (1.0 / dist2.sqrt()) * (ATTRACTOR_STRENGTH / (dist2 + ATTRACTOR_SOFTENING))
+}
+
+// sends an ember towards each point of `message`, which it reaches as the
+// drag stops it
+fn spell(
+    pgroup: &mut ParticleGroup,
+    rocket: Particle,
+    message: &[Vector],
+    rng: &mut StdRng,
+) -> usize {
+    let reach = (1.0 / MESSAGE_DRAG).ln();
+    for (i, point) in message.iter().enumerate() {
+        // a little ragged, so the text comes apart as it falls
+        let jitter = Vector {
+            x: rng.gen_range(-0.5..0.5),
+            y: rng.gen_range(-0.5..0.5),
+        };
+        let vel = (*point) * reach + jitter;
+        let lifetime = rocket.burst.lifetime(rng);
+        match pgroup.spawn(Particle::new(1, rocket.pos, vel, rocket.burst)) {
+            Some(ember) => ember.lifetime = lifetime,
+            None => return i,
+        }
+    }
+    message.len()
+}
+
+// `rocket` is the particle going off, rocket or charged ember
+fn explode(
+    pgroup: &mut ParticleGroup,
+    rocket: Particle,
+    effect: &dyn Effect,
+    max_depth: u8,
+    base: usize,
+    size: f64,
+    rng: &mut StdRng,
+) -> usize {
+    let (pos, burst, generation) = (rocket.pos, rocket.burst, rocket.generation);
+    // every stage is half the size and half the density of the one before it
+    let scale = 0.5f64.powi(i32::from(generation)) * size.clamp(BURST_SPREAD[0], BURST_SPREAD[1]);
+    // a crossette's stars split into a cross, whatever their size
+    let split = burst == BurstType::Crossette && generation > 0;
+    let (n, pattern): (usize, &dyn Effect) = match split {
+        true => (4, &BurstType::Ring),
+        // a burst asked to be bigger than MAX_BURST still gets to double
+        false => (
+            ((base as f64 * size * rocket.shell).round() as usize >> generation)
+                .clamp(MIN_BURST, MAX_BURST.max(2 * base)),
+            effect,
+        ),
+    };
+    let offset = rng.gen_range(0.0..2.0 * PI);
+    let charge = generation + 1 < max_depth.max(effect.min_depth());
+    let stride = (n / CHARGED_PER_BURST).max(1);
+    // every star of a crossette splits, other bursts only charge a few
+    let charged = |i: usize| match burst {
+        BurstType::Crossette => true,
+        _ => i.is_multiple_of(stride) && i / stride < CHARGED_PER_BURST,
+    };
+    for i in 0..n {
+        let vel = pattern.velocity(i, n, offset, rng) * scale;
+        let lifetime = effect.lifetime(rng);
+        // a full group drops the rest of the burst rather than cutting
+        // short particles that are already flying
+        let ember = match pgroup.spawn(Particle::new(generation + 1, pos, vel, burst)) {
+            Some(ember) => ember,
+            None => return i,
+        };
+        ember.lifetime = lifetime;
+        if charge && charged(i) {
+            ember.charge();
+        }
+    }
+    n
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::particle::Flicker;
+    use super::*;
+
+    fn show(seed: u64) -> Vec<(f64, f64, Color)> {
+        let mut world = World::new();
+        world.set_seed(seed);
+        world.set_floor(-30.0);
+        world.set_depth(MAX_DEPTH);
+        for i in 0..6 {
+            let pos = Vector {
+                x: f64::from(i) * 10.0 - 25.0,
+                y: -30.0,
+            };
+            world.launch(i as usize, pos, Vector { x: 2.0, y: 40.0 });
+        }
+        for _ in 0..150 {
+            world.step(REFERENCE_DT);
+        }
+        world.particles().collect()
+    }
+
+    #[test]
+    fn same_seed_same_show() {
+        let first = show(7);
+        assert!(first.len() > 6, "nothing exploded");
+        assert_eq!(first, show(7));
+        assert_ne!(first, show(8));
+    }
+
+    #[test]
+    fn rockets_burst_without_gravity() {
+        let mut world = World::new();
+        world.set_seed(5);
+        world.set_gravity(0.0);
+        world.listen();
+        world.launch(0, Vector::zero(), Vector { x: 0.0, y: 40.0 });
+        let mut steps = 0;
+        while world.particle_count() > 0 {
+            world.step(REFERENCE_DT);
+            steps += 1;
+            assert!(steps < 3000, "the rocket never went away");
+        }
+        let events = world.take_events();
+        let bursts = events
+            .iter()
+            .filter(|event| matches!(event, Event::Burst { generation: 0, .. }));
+        assert_eq!(bursts.count(), 1);
+    }
+
+    #[test]
+    fn missing_colors_launch_nothing() {
+        let mut world = World::with_palette(&[Color::Red]);
+        world.listen();
+        world.launch(1, Vector::zero(), Vector { x: 0.0, y: 40.0 });
+        world.launch_burst(
+            7,
+            Vector::zero(),
+            Vector { x: 0.0, y: 40.0 },
+            BurstType::Ring,
+        );
+        assert_eq!(world.particle_count(), 0);
+        assert_eq!(world.stats().launches, 0);
+        assert!(world.take_events().is_empty());
+    }
+
+    #[test]
+    fn repainting_keeps_what_is_flying() {
+        let mut world = World::with_palette(&[Color::Red, Color::Green]);
+        world.set_max_particles(50);
+        world.launch(0, Vector::zero(), Vector { x: 0.0, y: 40.0 });
+        world.set_palette(&[Color::Blue, Color::Yellow, Color::White]);
+        assert_eq!(world.palette(), [Color::Blue, Color::Yellow, Color::White]);
+        assert_eq!(world.max_particles(), 50);
+        assert_eq!(
+            world.particles().next().map(|(_, _, c)| c),
+            Some(Color::Blue)
+        );
+        world.set_palette(&[Color::Cyan]);
+        assert_eq!(world.palette(), [Color::Cyan]);
+        assert_eq!(world.particle_count(), 1);
+    }
+
+    #[test]
+    fn vortexes_fall_back_into_themselves() {
+        // how far the embers of a burst end up from where it went off
+        let spread = |burst: BurstType| {
+            let mut world = World::new();
+            world.set_seed(4);
+            world.launch_burst(0, Vector::zero(), Vector { x: 0.0, y: 40.0 }, burst);
+            for _ in 0..250 {
+                world.step(REFERENCE_DT);
+            }
+            let apex = world.particle_groups[0].particles[0].origin;
+            let far = world
+                .particles()
+                .map(|(x, y, _)| ((x - apex.x).powi(2) + (y - apex.y).powi(2)).sqrt());
+            far.fold(0.0, f64::max)
+        };
+        let (vortex, ring) = (spread(BurstType::Vortex), spread(BurstType::Ring));
+        assert!(vortex < ring / 2.0, "{} {}", vortex, ring);
+    }
+
+    #[test]
+    fn wells_pull_sparks_in_and_push_them_out() {
+        let drift = |strength: f64| {
+            let mut world = World::new();
+            world.set_gravity(0.0);
+            world.add_well(Well {
+                pos: Vector { x: 10.0, y: 0.0 },
+                strength,
+                spin: 0.0,
+                radius: 20.0,
+            });
+            world.add_fountain(FountainEmitter {
+                pos: Vector::zero(),
+                rate: 200.0,
+                spread: 0.0,
+                color_index: 0,
+            });
+            for _ in 0..30 {
+                world.step(REFERENCE_DT);
+            }
+            let xs: Vec<f64> = world.particles().map(|(x, _, _)| x).collect();
+            xs.iter().sum::<f64>() / xs.len() as f64
+        };
+        assert!(drift(100.0) > 0.5, "{}", drift(100.0));
+        assert!(drift(-100.0) < -0.5, "{}", drift(-100.0));
+        assert_eq!(drift(0.0), 0.0);
+    }
+
+    #[test]
+    fn keeps_count_of_the_show() {
+        let mut world = World::new();
+        world.set_seed(2);
+        world.launch_burst(
+            0,
+            Vector::zero(),
+            Vector { x: 0.0, y: 40.0 },
+            BurstType::Ring,
+        );
+        for _ in 0..150 {
+            world.step(REFERENCE_DT);
+        }
+        let stats = world.stats();
+        assert_eq!((stats.launches, stats.bursts), (1, 1));
+        // every ember of the one burst was alive at once, right after it
+        assert!(stats.sparks > 0);
+        assert!(stats.peak_particles as u64 >= stats.sparks, "{:?}", stats);
+    }
+
+    #[test]
+    fn wind_blows_everything_downwind() {
+        let mean_x = |wind: f64| {
+            let mut world = World::new();
+            world.set_seed(1);
+            world.set_wind(wind);
+            world.set_turbulence(20.0);
+            world.launch(0, Vector::zero(), Vector { x: 0.0, y: 40.0 });
+            for _ in 0..200 {
+                world.step(REFERENCE_DT);
+            }
+            let xs: Vec<f64> = world.particles().map(|(x, _, _)| x).collect();
+            xs.iter().sum::<f64>() / xs.len() as f64
+        };
+        assert!(mean_x(20.0) > 10.0);
+        assert!(mean_x(-20.0) < -10.0);
+        assert!(mean_x(0.0).abs() < 5.0);
+    }
+
+    #[test]
+    fn smoke_rises_and_clears_without_moving_the_sparks() {
+        let run = |smoke: bool| {
+            let mut world = World::new();
+            world.set_seed(3);
+            if smoke {
+                world.toggle_smoke();
+            }
+            world.launch(0, Vector::zero(), Vector { x: 0.0, y: 40.0 });
+            let mut heights = Vec::new();
+            for _ in 0..800 {
+                world.step(REFERENCE_DT);
+                heights.push(world.smoke().map(|(_, y, _)| y).fold(f64::MIN, f64::max));
+            }
+            (world.particles().count(), world.smoke().count(), heights)
+        };
+        let (sparks, _, _) = run(false);
+        let (smoky_sparks, left, heights) = run(true);
+        assert_eq!(sparks, smoky_sparks);
+        // the burst is at about y = 20, after a second of climbing
+        assert!(heights[300] > 22.0, "{}", heights[300]);
+        assert_eq!(left, 0);
+    }
+
+    #[test]
+    fn shells_come_in_many_sizes_and_heights() {
+        // the size and height of each of a row of identical rockets' bursts
+        let bursts = |world: &mut World| {
+            world.set_seed(3);
+            world.listen();
+            world.burst = Some(BurstType::Peony);
+            for i in 0..12 {
+                let pos = Vector {
+                    x: i as f64 * 5.0,
+                    y: 0.0,
+                };
+                world.launch(i % 6, pos, Vector { x: 0.0, y: 40.0 });
+            }
+            for _ in 0..200 {
+                world.step(REFERENCE_DT);
+            }
+            let mut bursts: Vec<(usize, f64)> = world
+                .take_events()
+                .into_iter()
+                .filter_map(|event| match event {
+                    Event::Burst { embers, pos, .. } => Some((embers, pos.y)),
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(bursts.len(), 12);
+            bursts.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            bursts
+        };
+        let mixed = bursts(&mut World::new());
+        assert!(mixed[11].0 >= 2 * mixed[0].0, "{:?}", mixed);
+        let heights: Vec<f64> = mixed.iter().map(|(_, y)| *y).collect();
+        let (low, high) = heights
+            .iter()
+            .fold((f64::MAX, f64::MIN), |(l, h), y| (l.min(*y), h.max(*y)));
+        assert!(high - low > 1.0, "{:?}", heights);
+
+        let mut world = World::new();
+        world.set_shell_sizes(1.0, 1.0);
+        world.set_apex_fuzz(0.0);
+        let same = bursts(&mut world);
+        assert!(same.iter().all(|b| *b == same[0]), "{:?}", same);
+    }
+
+    #[test]
+    fn embers_meet_the_ground_and_the_sides() {
+        // where the sparks of a burst low over the floor are after a while
+        let run = |ground: Ground, wrap: Option<f64>, steps: usize| {
+            let mut world = World::new();
+            world.set_seed(4);
+            world.set_floor(-10.0);
+            world.set_ground(ground);
+            world.set_wrap(wrap);
+            world.toggle_trails();
+            let pos = Vector { x: 0.0, y: -10.0 };
+            world.launch_burst(0, pos, Vector { x: 15.0, y: 20.0 }, BurstType::Willow);
+            for _ in 0..steps {
+                world.step(REFERENCE_DT);
+            }
+            world
+                .particles()
+                .map(|(x, y, _)| (x, y))
+                .collect::<Vec<_>>()
+        };
+        let through = run(Ground::FallThrough, None, 250);
+        let slid = run(Ground::Slide, None, 250);
+        assert!(slid.len() > through.len());
+        assert!(slid.iter().all(|(_, y)| *y >= -10.0));
+        assert!(slid.iter().filter(|(_, y)| *y == -10.0).count() > slid.len() / 2);
+        // they burn out soon after coming down
+        assert!(run(Ground::Slide, None, 400).is_empty());
+
+        let wrapped = run(Ground::Bounce, Some(20.0), 150);
+        assert!(!wrapped.is_empty());
+        assert!(wrapped.iter().all(|(x, _)| (-10.0..10.0).contains(x)));
+        assert!(run(Ground::Bounce, None, 150)
+            .iter()
+            .any(|(x, _)| *x >= 10.0));
+    }
+
+    #[test]
+    fn shaped_bursts_keep_their_outline() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let n = 40;
+        let speeds = |burst: BurstType, rng: &mut StdRng| -> Vec<f64> {
+            (0..n)
+                .map(|i| {
+                    let vel = burst.velocity(i, n, 1.0, rng);
+                    (vel.x.powi(2) + vel.y.powi(2)).sqrt()
+                })
+                .collect()
+        };
+        // a star's fastest embers are its five tips, the slowest its notches
+        let star = speeds(BurstType::Star, &mut rng);
+        let fastest = star.iter().cloned().fold(0.0, f64::max);
+        assert_eq!(star.iter().filter(|s| **s > fastest * 0.95).count(), 5);
+        assert!(star.iter().any(|s| *s < fastest * 0.5));
+        // a smiley's face is round, with its eyes and mouth inside it
+        let smiley = speeds(BurstType::Smiley, &mut rng);
+        let face = smiley.iter().filter(|s| (**s - 30.0).abs() < 1e-9).count();
+        assert_eq!(face, n / 2);
+        assert!(smiley.iter().all(|s| *s <= 30.0 + 1e-9));
+    }
+
+    #[test]
+    fn embers_glitter_before_going_out() {
+        let mut world = World::new();
+        world.set_seed(9);
+        world.toggle_trails();
+        let vel = Vector { x: 0.0, y: 40.0 };
+        world.launch_burst(0, Vector::zero(), vel, BurstType::Willow);
+        let glittering = |world: &World| {
+            let embers = world.particle_groups[0].particles.iter();
+            embers.filter(|p| p.flicker != Flicker::Steady).count()
+        };
+        // the rocket bursts after about a second, then its embers burn
+        // steadily until well into their last 1.5s
+        for _ in 0..150 {
+            world.step(REFERENCE_DT);
+        }
+        assert!(world.particle_count() > 10);
+        assert_eq!(glittering(&world), 0);
+        for _ in 0..170 {
+            world.step(REFERENCE_DT);
+        }
+        assert!(glittering(&world) > world.particle_count() / 2);
+    }
+
+    #[test]
+    fn steady_sparks_never_flash() {
+        let flashes = |flashing: bool| {
+            let mut world = World::new();
+            world.set_seed(9);
+            world.set_flashing(flashing);
+            let vel = Vector { x: 0.0, y: 40.0 };
+            world.launch_burst(0, Vector::zero(), vel, BurstType::Willow);
+            let mut flashes = 0;
+            for _ in 0..400 {
+                world.step(REFERENCE_DT);
+                let embers = world.particle_groups[0].particles.iter();
+                flashes += embers.filter(|p| p.flicker != Flicker::Steady).count();
+                let white = |(_, _, c): &(f64, f64, Color)| {
+                    matches!(c, Color::White | Color::Rgb(255, 255, 255))
+                };
+                flashes += world.particles().filter(white).count();
+            }
+            flashes
+        };
+        assert!(flashes(true) > 100);
+        assert_eq!(flashes(false), 0);
+    }
+
+    struct Sparkler;
+
+    impl Effect for Sparkler {
+        fn particle_count(&self) -> usize {
+            10
+        }
+
+        fn velocity(&self, _: usize, _: usize, _: f64, _: &mut StdRng) -> Vector {
+            Vector { x: 0.0, y: 10.0 }
+        }
+    }
+
+    #[test]
+    fn registered_effects_take_their_turn() {
+        let mut world = World::new();
+        let sparkler = world.register_effect("sparkler", Sparkler);
+        assert_eq!(world.burst_named("sparkler"), Some(sparkler));
+        assert_eq!(world.burst_named("heart"), Some(BurstType::Heart));
+        for _ in 0..BURSTS.len() + 1 {
+            world.cycle_burst();
+        }
+        assert_eq!(world.burst, Some(sparkler));
+        world.cycle_burst();
+        assert_eq!(world.burst, None);
+
+        // bursting right at the apex, so the exhaust is out in time
+        world.set_apex_fuzz(0.0);
+        world.launch_burst(0, Vector::zero(), Vector { x: 0.0, y: 40.0 }, sparkler);
+        for _ in 0..160 {
+            world.step(REFERENCE_DT);
+        }
+        // every ember flew straight up, and the exhaust has burnt out
+        let xs: Vec<f64> = world.particles().map(|(x, _, _)| x).collect();
+        assert!(xs.len() >= 10);
+        assert!(xs.iter().all(|x| x.abs() < 1e-9));
+    }
+
+    #[test]
+    fn events_only_once_listened_for() {
+        let mut world = World::new();
+        world.launch(0, Vector::zero(), Vector { x: 0.0, y: 40.0 });
+        world.step(REFERENCE_DT);
+        assert!(world.take_events().is_empty());
+
+        world.listen();
+        let vel = Vector { x: 0.0, y: 40.0 };
+        world.launch_burst(0, Vector::zero(), vel, BurstType::Ring);
+        assert_eq!(
+            world.take_events(),
+            [Event::Launch {
+                color_index: 0,
+                pos: Vector::zero(),
+                vel,
+                burst: BurstType::Ring
+            }]
+        );
+        for _ in 0..200 {
+            world.step(REFERENCE_DT);
+        }
+        let bursts = world
+            .take_events()
+            .into_iter()
+            .filter(|e| matches!(e, Event::Burst { generation: 0, embers, .. } if *embers > 0))
+            .count();
+        assert_eq!(bursts, 2);
+        assert!(world.take_events().is_empty());
+    }
+}
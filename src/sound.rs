@@ -0,0 +1,252 @@
+use firewors::sim::Event;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rodio::{ChannelCount, DeviceSinkBuilder, SampleRate, Source};
+use std::{
+    io,
+    sync::mpsc::{self, Receiver, Sender, TryRecvError},
+    thread,
+    time::Duration,
+};
+
+// mono at this rate, which rodio converts to whatever the output takes
+const RATE: usize = 22050;
+// samples mixed before looking for new voices, about 12ms
+const BLOCK: usize = 256;
+// voices playing at once, a finale would drown in more anyway
+const MAX_VOICES: usize = 24;
+// how long each sound lasts, in seconds
+const WHOOSH_SECS: f32 = 0.5;
+const BANG_SECS: f32 = 1.4;
+
+/// Whooshes and bangs for the simulation's events, mixed as the system's
+/// audio output asks for them.
+pub struct Sound {
+    voices: Sender<Voice>,
+    volume: f32,
+    muted: bool,
+    // dropped along with the sound, which closes the output
+    _open: Sender<()>,
+}
+
+impl Sound {
+    /// Opens the default audio output; `volume` goes from 0 to 1.
+    pub fn open(volume: f64) -> io::Result<Sound> {
+        let (voices, rx) = mpsc::channel();
+        let (_open, closed) = mpsc::channel();
+        let (opened, result) = mpsc::channel();
+        // the output stays on the thread that opened it, which not every
+        // platform lets it leave
+        thread::spawn(move || {
+            let sink = match DeviceSinkBuilder::open_default_sink() {
+                Ok(mut sink) => {
+                    sink.log_on_drop(false);
+                    let _ = opened.send(Ok(()));
+                    sink
+                }
+                Err(err) => {
+                    let _ = opened.send(Err(io::Error::other(err)));
+                    return;
+                }
+            };
+            sink.mixer().add(Mix::new(rx));
+            let _ = closed.recv();
+        });
+        result
+            .recv()
+            .map_err(|_| io::Error::other("the audio output went away"))??;
+        Ok(Sound {
+            voices,
+            volume: volume as f32,
+            muted: false,
+            _open,
+        })
+    }
+
+    pub fn toggle_mute(&mut self) {
+        self.muted = !self.muted;
+    }
+
+    pub fn is_muted(&self) -> bool {
+        self.muted
+    }
+
+    pub fn play(&self, event: Event) {
+        if self.muted {
+            return;
+        }
+        let voice = match event {
+            Event::Launch { .. } => Voice::new(Kind::Whoosh, 0.4 * self.volume),
+            Event::Burst {
+                generation, embers, ..
+            } => {
+                // later stages are smaller and further off
+                let size = (embers as f32 / 150.0).min(1.0);
+                let gain = (0.4 + 0.6 * size) * 0.5f32.powi(i32::from(generation));
+                Voice::new(Kind::Bang, gain * self.volume)
+            }
+        };
+        // the mixer only stops once the output has gone away
+        let _ = self.voices.send(voice);
+    }
+}
+
+#[derive(Copy, Clone)]
+enum Kind {
+    // a rocket climbing: noise that gets brighter as it goes up
+    Whoosh,
+    // a thump followed by crackling that thins out
+    Bang,
+}
+
+struct Voice {
+    kind: Kind,
+    gain: f32,
+    age: usize,
+    len: usize,
+    // low-passed noise and the click the crackle is on
+    low: f32,
+    crack: f32,
+}
+
+impl Voice {
+    fn new(kind: Kind, gain: f32) -> Voice {
+        let secs = match kind {
+            Kind::Whoosh => WHOOSH_SECS,
+            Kind::Bang => BANG_SECS,
+        };
+        Voice {
+            kind,
+            gain,
+            age: 0,
+            len: (secs * RATE as f32) as usize,
+            low: 0.0,
+            crack: 0.0,
+        }
+    }
+
+    fn is_over(&self) -> bool {
+        self.age >= self.len
+    }
+
+    fn sample(&mut self, rng: &mut StdRng) -> f32 {
+        let t = self.age as f32 / self.len as f32;
+        let noise = rng.gen_range(-1.0..1.0);
+        self.age += 1;
+        match self.kind {
+            Kind::Whoosh => {
+                self.low += (0.02 + 0.2 * t) * (noise - self.low);
+                let envelope = (t * std::f32::consts::PI).sin() * (1.0 - t);
+                self.low * envelope * 4.0 * self.gain
+            }
+            Kind::Bang => {
+                self.low += 0.15 * (noise - self.low);
+                let thump = (-t * BANG_SECS / 0.08).exp();
+                if rng.gen::<f32>() < 0.004 * (1.0 - t) {
+                    self.crack = if rng.gen() { 1.0 } else { -1.0 };
+                }
+                self.crack *= 0.8;
+                (self.low * thump * 3.0 + self.crack * 0.6 * (1.0 - t)) * self.gain
+            }
+        }
+    }
+}
+
+// the voices playing, summed into one stream until nothing can send voices
+// any more and the last one is over
+struct Mix {
+    rx: Receiver<Voice>,
+    voices: Vec<Voice>,
+    rng: StdRng,
+    // samples until it looks for new voices
+    until_block: usize,
+    open: bool,
+}
+
+impl Mix {
+    fn new(rx: Receiver<Voice>) -> Mix {
+        Mix {
+            rx,
+            voices: Vec::new(),
+            rng: StdRng::from_entropy(),
+            until_block: 0,
+            open: true,
+        }
+    }
+
+    fn take_voices(&mut self) {
+        loop {
+            match self.rx.try_recv() {
+                Ok(voice) => {
+                    if self.voices.len() == MAX_VOICES {
+                        self.voices.remove(0);
+                    }
+                    self.voices.push(voice);
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.open = false;
+                    break;
+                }
+            }
+        }
+        self.voices.retain(|v| !v.is_over());
+    }
+}
+
+impl Iterator for Mix {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.until_block == 0 {
+            self.take_voices();
+            self.until_block = BLOCK;
+        }
+        if !self.open && self.voices.is_empty() {
+            return None;
+        }
+        self.until_block -= 1;
+        let sum: f32 = self
+            .voices
+            .iter_mut()
+            .map(|v| v.sample(&mut self.rng))
+            .sum();
+        // a soft limit keeps a busy sky from clipping
+        Some(sum.tanh())
+    }
+}
+
+impl Source for Mix {
+    fn current_span_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> ChannelCount {
+        ChannelCount::MIN
+    }
+
+    fn sample_rate(&self) -> SampleRate {
+        SampleRate::new(RATE as u32).expect("the rate isn't zero")
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plays_on_until_the_last_voice_is_over() {
+        let (voices, rx) = mpsc::channel();
+        let mut mix = Mix::new(rx);
+        voices.send(Voice::new(Kind::Bang, 1.0)).unwrap();
+        drop(voices);
+        let samples: Vec<f32> = mix.by_ref().take(RATE * 3).collect();
+        let bang = (BANG_SECS * RATE as f32) as usize;
+        assert!(samples.len() >= bang && samples.len() < bang + BLOCK);
+        assert!(samples.iter().all(|s| (-1.0..=1.0).contains(s)));
+        assert!(samples.iter().any(|s| s.abs() > 0.1));
+    }
+}
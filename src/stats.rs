@@ -0,0 +1,79 @@
+use crate::countdown::clock;
+use firewors::sim::Stats;
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    time::Duration,
+};
+
+/// How a show went, from the simulation's counts and how long it ran.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Summary {
+    pub ran: Duration,
+    pub frames: u64,
+    pub sim: Stats,
+}
+
+impl Summary {
+    /// A line for each number, for the screen shown on quitting.
+    pub fn lines(&self) -> Vec<String> {
+        let fps = self.frames as f64 / self.ran.as_secs_f64().max(f64::EPSILON);
+        [
+            ("ran for", clock(Duration::from_secs(self.ran.as_secs()))),
+            ("launches", self.sim.launches.to_string()),
+            ("bursts", self.sim.bursts.to_string()),
+            ("sparks", self.sim.sparks.to_string()),
+            ("most at once", self.sim.peak_particles.to_string()),
+            ("frames", format!("{} at {:.0} fps", self.frames, fps)),
+        ]
+        .iter()
+        .map(|(name, value)| format!("{:<14}{}", name, value))
+        .collect()
+    }
+
+    /// One JSON object with every number in it.
+    pub fn json(&self) -> String {
+        format!(
+            r#"{{"duration_secs": {:.3}, "frames": {}, "launches": {}, "bursts": {}, "sparks": {}, "peak_particles": {}}}"#,
+            self.ran.as_secs_f64(),
+            self.frames,
+            self.sim.launches,
+            self.sim.bursts,
+            self.sim.sparks,
+            self.sim.peak_particles
+        )
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        writeln!(out, "{}", self.json())?;
+        out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_same_on_screen_and_in_json() {
+        let summary = Summary {
+            ran: Duration::from_millis(83_250),
+            frames: 4995,
+            sim: Stats {
+                launches: 12,
+                bursts: 30,
+                sparks: 2400,
+                peak_particles: 800,
+            },
+        };
+        assert_eq!(
+            summary.json(),
+            r#"{"duration_secs": 83.250, "frames": 4995, "launches": 12, "bursts": 30, "sparks": 2400, "peak_particles": 800}"#
+        );
+        let lines = summary.lines();
+        assert_eq!(lines[0], "ran for       01:23");
+        assert_eq!(lines[5], "frames        4995 at 60 fps");
+    }
+}
@@ -0,0 +1,506 @@
+// drives the main loop against a TestBackend, one tick at a time
+use super::*;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use firewors::sim::REFERENCE_DT;
+use ratatui::backend::TestBackend;
+use std::{env, fs, path::PathBuf};
+
+fn start(args: &[&str], width: u16, height: u16) -> (Session, Terminal<TestBackend>) {
+    let args = Args::parse_from(["firewors"].iter().chain(args));
+    let app = App::new(Config::default(), &args);
+    let terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+    let session = Session::new(app, terminal.size().unwrap().into()).unwrap();
+    (session, terminal)
+}
+
+fn press(session: &mut Session, key: char) -> bool {
+    let key: KeyEvent = KeyCode::Char(key).into();
+    session.event(Event::Key(key)).unwrap()
+}
+
+fn run(session: &mut Session, ticks: u64) {
+    for _ in 0..ticks {
+        assert!(session.step(REFERENCE_DT).unwrap());
+    }
+}
+
+// the characters on screen, a line per row
+fn frame(session: &mut Session, terminal: &mut Terminal<TestBackend>) -> String {
+    session.draw(terminal).unwrap();
+    let buffer = terminal.backend().buffer();
+    let width = usize::from(buffer.area.width);
+    let symbols: Vec<&str> = buffer.content.iter().map(|c| c.symbol()).collect();
+    symbols
+        .chunks(width)
+        .map(|row| row.concat() + "\n")
+        .collect()
+}
+
+// compares against src/snapshots/<name>.txt, rewriting it instead when
+// UPDATE_SNAPSHOTS is set
+fn snapshot(name: &str, frame: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("src/snapshots")
+        .join(format!("{}.txt", name));
+    if env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::write(&path, frame).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("no {}, run with UPDATE_SNAPSHOTS=1", path.display()));
+    assert!(
+        expected == frame,
+        "{} changed:\n{}\nrun with UPDATE_SNAPSHOTS=1 if that's intended",
+        name,
+        frame
+    );
+}
+
+#[test]
+fn rocket_climbs_then_bursts() {
+    let (mut session, mut terminal) = start(&["--seed", "1"], 60, 20);
+    assert!(press(&mut session, 'f'));
+    run(&mut session, 30);
+    snapshot("rocket_climbing", &frame(&mut session, &mut terminal));
+    run(&mut session, 120);
+    snapshot("rocket_burst", &frame(&mut session, &mut terminal));
+}
+
+#[test]
+fn multi_launch_with_hud() {
+    let (mut session, mut terminal) = start(&["--seed", "2", "--depth", "2"], 80, 24);
+    press(&mut session, 'h');
+    press(&mut session, 'm');
+    run(&mut session, 200);
+    snapshot("multi_launch", &frame(&mut session, &mut terminal));
+}
+
+#[test]
+fn same_seed_same_frames() {
+    let mut frames = Vec::new();
+    for _ in 0..2 {
+        let (mut session, mut terminal) = start(&["--seed", "3", "--auto"], 60, 20);
+        press(&mut session, 'g');
+        run(&mut session, 300);
+        frames.push(frame(&mut session, &mut terminal));
+    }
+    assert_eq!(frames[0], frames[1]);
+}
+
+#[test]
+fn physics_keeps_its_own_time() {
+    let (session, _) = start(&["--seed", "7"], 40, 15);
+    let (session, done) = (Mutex::new(session), AtomicBool::new(false));
+    thread::scope(|s| {
+        let physics = s.spawn(|| simulate(&session, &done, Duration::from_millis(1)));
+        thread::sleep(Duration::from_millis(100));
+        done.store(true, Ordering::Relaxed);
+        physics.join().unwrap().unwrap();
+    });
+    assert!(session.lock().unwrap().ticks >= 20);
+}
+
+#[test]
+fn quit_ends_the_session() {
+    let (mut session, _) = start(&[], 40, 15);
+    assert!(press(&mut session, 'f'));
+    assert!(!press(&mut session, 'q'));
+}
+
+#[test]
+fn quitting_sums_up_the_show() {
+    let (mut session, mut terminal) = start(&["--seed", "3"], 60, 20);
+    press(&mut session, 'f');
+    run(&mut session, 400);
+    frame(&mut session, &mut terminal);
+    assert!(!session.app.quit);
+    press(&mut session, 'q');
+    assert!(session.app.quit);
+
+    let summary = session.summary();
+    assert_eq!((summary.frames, summary.sim.launches), (1, 1));
+    assert!(summary.sim.bursts >= 1);
+    terminal.draw(|f| summary_ui(f, &summary.lines())).unwrap();
+    let buffer = terminal.backend().buffer();
+    let text: String = buffer.content.iter().map(|c| c.symbol()).collect();
+    assert!(text.contains("show over"));
+    assert!(text.contains("launches      1"));
+}
+
+#[test]
+fn holds_while_too_small() {
+    let (mut session, mut terminal) = start(&["--seed", "4"], 40, 15);
+    press(&mut session, 'f');
+    terminal.backend_mut().resize(8, 5);
+    assert!(frame(&mut session, &mut terminal).contains("terminal"));
+    terminal.backend_mut().resize(50, 20);
+    frame(&mut session, &mut terminal);
+    assert_eq!(session.screen.size, Rect::new(0, 0, 50, 20));
+}
+
+#[test]
+fn remapped_keys_take_modifiers() {
+    let mut config = Config::default();
+    let keys = ["ctrl+l", "F5"].map(|name| keymap::parse_key(name).unwrap());
+    config.keymap.bind(Action::Launch, &keys);
+    let args = Args::parse_from(["firewors"]);
+    let terminal = Terminal::new(TestBackend::new(40, 15)).unwrap();
+    let app = App::new(config, &args);
+    let mut session = Session::new(app, terminal.size().unwrap().into()).unwrap();
+    let key = |code, modifiers| Event::Key(KeyEvent::new(code, modifiers));
+
+    // the plain key it had before does nothing now
+    press(&mut session, 'f');
+    press(&mut session, 'l');
+    assert_eq!(session.app.sim.particle_count(), 0);
+    session
+        .event(key(KeyCode::Char('l'), KeyModifiers::CONTROL))
+        .unwrap();
+    session
+        .event(key(KeyCode::F(5), KeyModifiers::NONE))
+        .unwrap();
+    assert_eq!(session.app.sim.particle_count(), 2);
+    assert_eq!(keymap::key_name(keys[0]), "^l");
+
+    // shift is in the character, and ctrl+c still quits
+    assert!(session
+        .event(key(KeyCode::Char('G'), KeyModifiers::SHIFT))
+        .unwrap());
+    assert!(!session
+        .event(key(KeyCode::Char('c'), KeyModifiers::CONTROL))
+        .unwrap());
+}
+
+#[test]
+fn budget_follows_the_screen_and_the_frame_rate() {
+    let (session, _) = start(&[], 300, 80);
+    assert_eq!(session.app.sim.max_particles(), 2828);
+    let (mut session, _) = start(&[], 80, 24);
+    assert_eq!(session.app.sim.max_particles(), 1000);
+    for _ in 0..100 {
+        session.app.frame_took(Duration::from_millis(50));
+    }
+    assert!(session.app.budget.load() < 0.5);
+    // fewer sparks held at once, as well as fewer in every burst
+    assert!(session.app.sim.max_particles() < 500);
+    for _ in 0..1000 {
+        session.app.frame_took(Duration::from_millis(2));
+    }
+    assert_eq!(session.app.budget.load(), 1.0);
+    assert_eq!(session.app.sim.max_particles(), 1000);
+
+    let (mut session, _) = start(&["--no-adapt"], 300, 80);
+    session.app.frame_took(Duration::from_millis(50));
+    assert_eq!(session.app.sim.max_particles(), 1000);
+    assert_eq!(session.app.budget.scale(), 1.0);
+}
+
+#[test]
+fn accessible_modes() {
+    let (session, _) = start(&["--reduced-motion"], 80, 24);
+    assert_eq!(session.app.time_scale, 0.5);
+
+    let (mut session, mut terminal) = start(&["--seed", "3", "--high-contrast"], 60, 20);
+    press(&mut session, 'f');
+    run(&mut session, 150);
+    let screen = frame(&mut session, &mut terminal);
+    assert!(screen.contains('▄'), "{}", screen);
+    // every spark drawn as bright as its hue goes
+    for cell in terminal.backend().buffer().content.iter() {
+        if let Color::Rgb(r, g, b) = cell.fg {
+            assert_eq!(r.max(g).max(b), 255, "{:?}", cell.fg);
+        }
+    }
+}
+
+#[test]
+fn ascii_mode_leaves_out_braille() {
+    let (mut session, mut terminal) = start(&["--seed", "3", "--ascii"], 60, 20);
+    press(&mut session, 'f');
+    run(&mut session, 150);
+    let screen = frame(&mut session, &mut terminal);
+    assert!(screen.contains('*'), "{}", screen);
+    assert!(
+        !screen
+            .chars()
+            .any(|c| ('\u{2800}'..='\u{28ff}').contains(&c)),
+        "{}",
+        screen
+    );
+    // `b` has nothing plainer to go to than its own stars
+    press(&mut session, 'b');
+    let screen = frame(&mut session, &mut terminal);
+    assert!(!screen.contains('•'), "{}", screen);
+}
+
+#[test]
+fn demo_captions_its_scenes() {
+    let (mut session, mut terminal) = start(&["--seed", "2"], 60, 20);
+    session.app.demo = Some(Demo::new(1.0, REFERENCE_DT, StdRng::seed_from_u64(3)));
+    run(&mut session, 10);
+    assert!(frame(&mut session, &mut terminal).contains("single rockets"));
+    run(&mut session, 100);
+    assert!(frame(&mut session, &mut terminal).contains("shaped bursts: peony"));
+    run(&mut session, 100);
+    assert!(frame(&mut session, &mut terminal).contains("themes: classic"));
+    assert!(session.app.sim.particle_count() > 0);
+}
+
+#[test]
+fn refuses_to_start_too_small() {
+    let args = Args::parse_from(["firewors"]);
+    let app = App::new(Config::default(), &args);
+    let err = Session::new(app, Rect::new(0, 0, 8, 5)).err().unwrap();
+    assert!(matches!(
+        err,
+        Error::TooSmall {
+            width: 8,
+            height: 5
+        }
+    ));
+    assert!(err.to_string().starts_with("the terminal is 8x5"));
+}
+
+#[test]
+fn errors_point_at_what_went_wrong_underneath() {
+    use std::error::Error as _;
+    let err = Error::from(io::Error::other("broken pipe"));
+    assert_eq!(err.to_string(), "broken pipe");
+    assert_eq!(err.source().unwrap().to_string(), "broken pipe");
+    let err = Error::open("show.toml")(io::Error::from(io::ErrorKind::NotFound));
+    assert_eq!(err.to_string(), "show.toml: entity not found");
+    assert!(err.source().is_some());
+    assert!(Error::Crashed.source().is_none());
+}
+
+#[test]
+fn message_bursts_into_text() {
+    let (mut session, mut terminal) = start(&["--seed", "5", "--message", "HI 2025"], 80, 24);
+    press(&mut session, 'w');
+    run(&mut session, 130);
+    snapshot("message", &frame(&mut session, &mut terminal));
+}
+
+#[test]
+fn bursts_over_the_town() {
+    let (mut session, mut terminal) = start(&["--seed", "6", "--scenery"], 60, 20);
+    press(&mut session, 'm');
+    run(&mut session, 150);
+    snapshot("scenery", &frame(&mut session, &mut terminal));
+}
+
+#[test]
+fn reflected_in_the_water() {
+    let (mut session, mut terminal) = start(&["--seed", "7", "--reflection", "6"], 60, 20);
+    press(&mut session, 'm');
+    run(&mut session, 150);
+    snapshot("reflection", &frame(&mut session, &mut terminal));
+}
+
+#[test]
+fn physics_keys_show_what_they_set() {
+    let (mut session, mut terminal) = start(&["--seed", "5"], 60, 20);
+    press(&mut session, ']');
+    press(&mut session, ']');
+    assert!(frame(&mut session, &mut terminal).contains("gravity 50"));
+    press(&mut session, ';');
+    assert!(frame(&mut session, &mut terminal).contains("drag 0.75x"));
+    press(&mut session, '-');
+    assert!(frame(&mut session, &mut terminal).contains("sparks 14"));
+    assert_eq!(session.app.sim.gravity().y, -50.0);
+    assert_eq!(session.app.sim.burst_particles(), Some(14));
+}
+
+#[test]
+fn aimed_from_the_launcher() {
+    let (mut session, mut terminal) = start(&["--seed", "6", "--aim"], 60, 20);
+    press(&mut session, 'z');
+    press(&mut session, 'z');
+    let left: KeyEvent = KeyCode::Left.into();
+    session.event(Event::Key(left)).unwrap();
+    snapshot("aim", &frame(&mut session, &mut terminal));
+
+    session.app.sim.listen();
+    let enter: KeyEvent = KeyCode::Enter.into();
+    session.event(Event::Key(enter)).unwrap();
+    let Some(sim::Event::Launch { pos, vel, .. }) = session.app.sim.take_events().pop() else {
+        panic!("nothing fired");
+    };
+    assert!((pos.x - -0.02 * 60.0).abs() < 1e-9);
+    assert!(vel.x < 0.0 && vel.y > 0.0);
+    // the wind stays where it was
+    assert_eq!(session.app.sim.wind(), 0.0);
+}
+
+#[test]
+fn recording_keeps_seed_and_size() {
+    let path = env::temp_dir().join(format!("firewors-{}.fwr", std::process::id()));
+    let mut recorder = Recorder::create(&path, Some(42), (100, 30), &Preset::default()).unwrap();
+    recorder.log(7, Input::Action(Action::Launch)).unwrap();
+    recorder
+        .log(9, Input::LaunchAt { column: 3, row: 4 })
+        .unwrap();
+    drop(recorder);
+    let mut replay = Replay::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(replay.seed, Some(42));
+    assert_eq!(replay.size, Some((100, 30)));
+    assert!(replay.due(6).is_empty());
+    assert_eq!(replay.due(9).len(), 2);
+}
+
+#[test]
+fn replays_with_the_settings_it_was_recorded_with() {
+    let path = env::temp_dir().join(format!("firewors-{}-settings.fwr", std::process::id()));
+    let path_arg = path.to_str().unwrap();
+    let mut args = Args::parse_from([
+        "firewors",
+        "--seed",
+        "9",
+        "--gravity",
+        "15",
+        "--wind",
+        "-8",
+        "--drag",
+        "2",
+        "--ground",
+        "bounce",
+        "--wrap",
+        "--tick-ms",
+        "5",
+        "--record",
+        path_arg,
+    ]);
+    replaying(&mut args).unwrap();
+    let mut app = App::new(Config::default(), &args);
+    open_session(&mut app, &args, (60, 20)).unwrap();
+    let mut terminal = Terminal::new(TestBackend::new(60, 20)).unwrap();
+    let mut session = Session::new(app, terminal.size().unwrap().into()).unwrap();
+    press(&mut session, 'f');
+    run(&mut session, 50);
+    terminal.backend_mut().resize(80, 24);
+    frame(&mut session, &mut terminal);
+    press(&mut session, 'f');
+    run(&mut session, 200);
+    assert!(!press(&mut session, 'q'));
+    let recorded: Vec<_> = session.app.sim.particles().collect();
+    drop(session);
+
+    // none of it given again, on a terminal of another size
+    let mut args = Args::parse_from(["firewors", "--replay", path_arg]);
+    let replay = replaying(&mut args).unwrap();
+    fs::remove_file(&path).unwrap();
+    let mut app = App::new(Config::default(), &args);
+    app.replay = replay;
+    open_session(&mut app, &args, (40, 15)).unwrap();
+    let mut session = Session::new(app, Rect::new(0, 0, 40, 15)).unwrap();
+    assert_eq!(session.screen.size, Rect::new(0, 0, 60, 20));
+    assert_eq!(args.tick_ms, Some(5));
+    assert_eq!(session.app.sim.gravity().y, -15.0);
+    assert_eq!(session.app.sim.wind(), -8.0);
+    assert_eq!(session.app.sim.ground(), Ground::Bounce);
+    while session.step(REFERENCE_DT).unwrap() {}
+    assert_eq!(session.ticks, 250);
+    assert_eq!(session.screen.size, Rect::new(0, 0, 80, 24));
+    assert!(!recorded.is_empty());
+    assert_eq!(session.app.sim.particles().collect::<Vec<_>>(), recorded);
+}
+
+#[test]
+fn records_what_the_demo_does() {
+    let path = env::temp_dir().join(format!("firewors-{}-demo.fwr", std::process::id()));
+    let mut args = Args::parse_from([
+        "firewors",
+        "--demo",
+        "1",
+        "--record",
+        path.to_str().unwrap(),
+    ]);
+    replaying(&mut args).unwrap();
+    let mut app = App::new(Config::default(), &args);
+    open_session(&mut app, &args, (60, 20)).unwrap();
+    let mut session = Session::new(app, Rect::new(0, 0, 60, 20)).unwrap();
+    run(&mut session, 250);
+    drop(session);
+    let log = fs::read_to_string(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert!(log.contains(" theme\n"), "{}", log);
+    assert!(log.contains(" launch\n"), "{}", log);
+    assert!(log.contains(" launch x="), "{}", log);
+    assert!(log.contains(" theme classic\n"), "{}", log);
+}
+
+// runs a second of the export to a file `name` in the temporary directory
+fn export_to(name: &str) -> Result<PathBuf, Error> {
+    let path = env::temp_dir().join(format!("firewors-{}-{name}", std::process::id()));
+    let args = Args::parse_from([
+        "firewors",
+        "--seed",
+        "6",
+        "export",
+        "-o",
+        path.to_str().unwrap(),
+        "--duration",
+        "1s",
+    ]);
+    let app = App::new(Config::default(), &args);
+    match &args.command {
+        Some(Command::Export(options)) => export::run(app, &args, options)?,
+        _ => unreachable!(),
+    }
+    Ok(path)
+}
+
+#[test]
+fn export_writes_a_gif() {
+    let path = export_to("export.gif").unwrap();
+    let gif = fs::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert!(gif.starts_with(b"GIF89a"));
+    // 80x24 cells at 2x4 dots of 2x2 pixels each
+    assert_eq!(gif[6..10], [64, 1, 192, 0]);
+    assert_eq!(gif.last(), Some(&0x3b));
+}
+
+#[test]
+fn export_writes_an_apng() {
+    let path = export_to("export.png").unwrap();
+    let png = fs::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+    assert_eq!(png[16..24], [0, 0, 1, 64, 0, 0, 0, 192]);
+    // the animation control chunk, with as many frames as a second holds
+    assert_eq!(&png[37..41], b"acTL");
+    let frames = u32::from_be_bytes(png[41..45].try_into().unwrap());
+    assert!(frames > 1);
+    assert!(png.ends_with(b"IEND\xae\x42\x60\x82"));
+}
+
+#[test]
+fn export_refuses_other_formats() {
+    assert!(matches!(export_to("export.mp4"), Err(Error::Export(_))));
+    assert!(matches!(export_to("export"), Err(Error::Export(_))));
+}
+
+#[test]
+fn screenshot_saves_text_and_png() {
+    let dir = env::temp_dir().join(format!("firewors-shots-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let (mut session, mut terminal) = start(&["--seed", "3"], 40, 12);
+    press(&mut session, 'f');
+    run(&mut session, 150);
+    session.draw(&mut terminal).unwrap();
+    let buffer = terminal.backend().buffer();
+    let first = screenshot::save(&session.app, buffer, &dir).unwrap();
+    let second = screenshot::save(&session.app, buffer, &dir).unwrap();
+    assert_ne!(first, second);
+    let text = fs::read_to_string(first.with_extension("ans")).unwrap();
+    let png = fs::read(first.with_extension("png")).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(text.lines().count(), 12);
+    assert!(text.contains("\x1b[0;"));
+    assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+    // 40x12 cells at 2x4 dots of 2x2 pixels each
+    assert_eq!(png[16..24], [0, 0, 0, 160, 0, 0, 0, 96]);
+    assert!(png.ends_with(b"IEND\xae\x42\x60\x82"));
+}
@@ -0,0 +1,161 @@
+use crate::countdown::{midnight_before, next_at, parse_clock};
+use crate::keymap::Action;
+use crate::record::Input;
+use std::time::{Duration, SystemTime};
+
+/// What a trigger sets off, as named in the config file.
+pub const FIRES: [(&str, Action); 3] = [
+    ("rocket", Action::Launch),
+    ("volley", Action::MultiLaunch),
+    ("finale", Action::Finale),
+];
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum When {
+    // every day when the local clock reads it
+    At([i64; 3]),
+    // on the multiples of it since local midnight
+    Every(Duration),
+}
+
+/// Sets something off at times of day on the wall clock, or every so often.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trigger {
+    when: When,
+    fire: Action,
+    // worked out the first time it's asked what's due
+    next: Option<SystemTime>,
+}
+
+impl Trigger {
+    /// A trigger going off `at` a time of day like `21:00` or `every` so
+    /// often like `30m` or `1h30m`, setting off one of FIRES.
+    pub fn parse(at: Option<&str>, every: Option<&str>, fire: &str) -> Result<Trigger, String> {
+        let when = match (at, every) {
+            (Some(at), None) => When::At(
+                parse_clock(at.trim())
+                    .ok_or_else(|| format!("`{}` is not a time like 21:00", at))?,
+            ),
+            (None, Some(every)) => When::Every(
+                parse_period(every)
+                    .ok_or_else(|| format!("`{}` is not a time like 30m or 1h30m", every))?,
+            ),
+            _ => return Err("needs either `at` or `every`".into()),
+        };
+        let fire = match FIRES.iter().find(|(name, _)| *name == fire) {
+            Some((_, action)) => *action,
+            None => {
+                let names: Vec<&str> = FIRES.iter().map(|(name, _)| *name).collect();
+                return Err(format!("can't fire `{}`, only {}", fire, names.join(", ")));
+            }
+        };
+        Ok(Trigger {
+            when,
+            fire,
+            next: None,
+        })
+    }
+
+    /// What goes off by `now`; times missed while the machine slept are let
+    /// go rather than all set off at once.
+    pub fn due(&mut self, now: SystemTime) -> Option<Input> {
+        let next = match self.next {
+            Some(next) => next,
+            None => {
+                self.next = self.after(now);
+                return None;
+            }
+        };
+        if now < next {
+            return None;
+        }
+        self.next = self.after(now);
+        Some(Input::Action(self.fire))
+    }
+
+    // the first time it goes off after `now`
+    fn after(&self, now: SystemTime) -> Option<SystemTime> {
+        match self.when {
+            When::At(clock) => next_at(clock, now),
+            When::Every(period) => {
+                let midnight = midnight_before(now)?;
+                let since = now.duration_since(midnight).ok()?;
+                let periods = since.as_secs() / period.as_secs() + 1;
+                Some(midnight + period * u32::try_from(periods).ok()?)
+            }
+        }
+    }
+}
+
+// hours, minutes and seconds run together like `1h30m`, at least a second
+fn parse_period(text: &str) -> Option<Duration> {
+    let mut secs = 0;
+    let mut number = String::new();
+    for c in text.trim().chars() {
+        let unit = match c {
+            '0'..='9' => {
+                number.push(c);
+                continue;
+            }
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return None,
+        };
+        let n: u64 = number.parse().ok()?;
+        secs += n.checked_mul(unit)?;
+        number.clear();
+    }
+    match number.is_empty() && secs > 0 {
+        true => Some(Duration::from_secs(secs)),
+        false => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn periods_read_like_durations() {
+        assert_eq!(parse_period("30m"), Some(Duration::from_secs(1800)));
+        assert_eq!(parse_period("1h30m"), Some(Duration::from_secs(5400)));
+        assert_eq!(parse_period("90s"), Some(Duration::from_secs(90)));
+        for text in ["", "0s", "30", "m", "1d", "-5m"] {
+            assert_eq!(parse_period(text), None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn goes_off_every_so_often() {
+        let mut trigger = Trigger::parse(None, Some("1m"), "volley").unwrap();
+        let start = midnight_before(SystemTime::now()).unwrap() + Duration::from_secs(3630);
+        let mut fired = 0;
+        for secs in 0..300 {
+            if let Some(input) = trigger.due(start + Duration::from_secs(secs)) {
+                assert_eq!(input, Input::Action(Action::MultiLaunch));
+                fired += 1;
+            }
+        }
+        // on the minute, not a minute after it started
+        assert_eq!(fired, 5);
+    }
+
+    #[test]
+    fn skips_what_was_slept_through() {
+        let mut trigger = Trigger::parse(Some("21:00"), None, "finale").unwrap();
+        let start = midnight_before(SystemTime::now()).unwrap() + Duration::from_secs(3600);
+        assert_eq!(trigger.due(start), None);
+        let later = start + Duration::from_secs(5 * 86400);
+        assert_eq!(trigger.due(later), Some(Input::Action(Action::Finale)));
+        assert_eq!(trigger.due(later + Duration::from_secs(1)), None);
+    }
+
+    #[test]
+    fn rejects_what_it_cant_go_by() {
+        assert!(Trigger::parse(None, None, "rocket").is_err());
+        assert!(Trigger::parse(Some("21:00"), Some("1h"), "rocket").is_err());
+        assert!(Trigger::parse(Some("25:00"), None, "rocket").is_err());
+        assert!(Trigger::parse(Some("21:00"), None, "quit").is_err());
+    }
+}
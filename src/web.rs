@@ -0,0 +1,346 @@
+use crate::sim::{Kind, Vector, World, REFERENCE_DT};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use ratatui::{
+    backend::{Backend, ClearType, WindowSize},
+    buffer::{Buffer, Cell},
+    layout::{Position, Rect, Size},
+    style::{Color, Modifier},
+    widgets::Widget,
+    Terminal,
+};
+use std::fmt::{self, Write};
+use wasm_bindgen::prelude::*;
+
+// seconds between two launches of the show going on by itself
+const LAUNCH_EVERY: (f64, f64) = (0.4, 1.6);
+// more than this at once is a page that was in the background
+const MAX_FRAME: f64 = 0.25;
+// below this many columns or rows nothing goes up, as in the program
+const MIN_SIZE: u16 = 10;
+
+/// A ratatui backend for xterm.js, or any terminal that is only handed
+/// text: what is drawn piles up as the escape codes for it until `take`.
+pub struct Xterm {
+    out: String,
+    size: Size,
+    cursor: Position,
+    // ratatui hides it again with every frame
+    hidden: bool,
+}
+
+impl Xterm {
+    pub fn new(width: u16, height: u16) -> Xterm {
+        Xterm {
+            out: String::new(),
+            size: Size { width, height },
+            cursor: Position::ORIGIN,
+            hidden: false,
+        }
+    }
+
+    pub fn resize(&mut self, width: u16, height: u16) {
+        self.size = Size { width, height };
+    }
+
+    /// Everything drawn since the last call, for `Terminal.write`.
+    pub fn take(&mut self) -> String {
+        std::mem::take(&mut self.out)
+    }
+}
+
+impl Backend for Xterm {
+    type Error = fmt::Error;
+
+    fn draw<'a, I>(&mut self, content: I) -> fmt::Result
+    where
+        I: Iterator<Item = (u16, u16, &'a Cell)>,
+    {
+        let mut at = None;
+        let mut style = None;
+        for (x, y, cell) in content {
+            if at != Some((x, y)) {
+                write!(self.out, "\x1b[{};{}H", y + 1, x + 1)?;
+            }
+            let own = (cell.fg, cell.bg, cell.modifier);
+            if style != Some(own) {
+                self.out.push_str("\x1b[0");
+                sgr(&mut self.out, cell.fg, false)?;
+                sgr(&mut self.out, cell.bg, true)?;
+                if cell.modifier.contains(Modifier::BOLD) {
+                    self.out.push_str(";1");
+                }
+                if cell.modifier.contains(Modifier::DIM) {
+                    self.out.push_str(";2");
+                }
+                self.out.push('m');
+                style = Some(own);
+            }
+            self.out.push_str(cell.symbol());
+            at = Some((x + 1, y));
+        }
+        if style.is_some() {
+            self.out.push_str("\x1b[0m");
+        }
+        Ok(())
+    }
+
+    fn hide_cursor(&mut self) -> fmt::Result {
+        if !self.hidden {
+            self.out.push_str("\x1b[?25l");
+            self.hidden = true;
+        }
+        Ok(())
+    }
+
+    fn show_cursor(&mut self) -> fmt::Result {
+        self.out.push_str("\x1b[?25h");
+        self.hidden = false;
+        Ok(())
+    }
+
+    fn get_cursor_position(&mut self) -> Result<Position, fmt::Error> {
+        Ok(self.cursor)
+    }
+
+    fn set_cursor_position<P: Into<Position>>(&mut self, position: P) -> fmt::Result {
+        self.cursor = position.into();
+        write!(
+            self.out,
+            "\x1b[{};{}H",
+            self.cursor.y + 1,
+            self.cursor.x + 1
+        )
+    }
+
+    fn clear(&mut self) -> fmt::Result {
+        self.clear_region(ClearType::All)
+    }
+
+    fn clear_region(&mut self, clear_type: ClearType) -> fmt::Result {
+        self.out.push_str(match clear_type {
+            ClearType::All => "\x1b[2J",
+            ClearType::AfterCursor => "\x1b[J",
+            ClearType::BeforeCursor => "\x1b[1J",
+            ClearType::CurrentLine => "\x1b[2K",
+            ClearType::UntilNewLine => "\x1b[K",
+        });
+        Ok(())
+    }
+
+    fn size(&self) -> Result<Size, fmt::Error> {
+        Ok(self.size)
+    }
+
+    // xterm.js keeps its cell size to itself, so this goes by a usual one
+    fn window_size(&mut self) -> Result<WindowSize, fmt::Error> {
+        Ok(WindowSize {
+            columns_rows: self.size,
+            pixels: Size {
+                width: self.size.width * 9,
+                height: self.size.height * 18,
+            },
+        })
+    }
+
+    fn flush(&mut self) -> fmt::Result {
+        Ok(())
+    }
+}
+
+// the select graphic rendition parameters for `color`
+fn sgr(out: &mut String, color: Color, background: bool) -> fmt::Result {
+    let base = if background { 40 } else { 30 };
+    let offset = match color {
+        Color::Reset => 9,
+        Color::Black => 0,
+        Color::Red => 1,
+        Color::Green => 2,
+        Color::Yellow => 3,
+        Color::Blue => 4,
+        Color::Magenta => 5,
+        Color::Cyan => 6,
+        Color::Gray => 7,
+        Color::DarkGray => 60,
+        Color::LightRed => 61,
+        Color::LightGreen => 62,
+        Color::LightYellow => 63,
+        Color::LightBlue => 64,
+        Color::LightMagenta => 65,
+        Color::LightCyan => 66,
+        Color::White => 67,
+        Color::Indexed(i) => return write!(out, ";{};5;{}", base + 8, i),
+        Color::Rgb(r, g, b) => return write!(out, ";{};2;{};{};{}", base + 8, r, g, b),
+    };
+    write!(out, ";{}", base + offset)
+}
+
+// the particles of `world`, a cell to a particle, with rockets drawn over
+// sparks and sparks over trails
+struct Sky<'a>(&'a World);
+
+impl Widget for Sky<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let rank = |kind| match kind {
+            Kind::Trail => 0,
+            Kind::Spark => 1,
+            Kind::Rocket => 2,
+        };
+        let mut particles: Vec<_> = self.0.particles_by_kind().collect();
+        particles.sort_by_key(|&(_, _, _, kind)| rank(kind));
+        let (width, height) = (f64::from(area.width), f64::from(area.height));
+        for (x, y, color, kind) in particles {
+            let column = (x + width / 2.0).floor();
+            let row = (height / 2.0 - y).floor();
+            if !(0.0..width).contains(&column) || !(0.0..height).contains(&row) {
+                continue;
+            }
+            let glyph = match kind {
+                Kind::Rocket => '^',
+                Kind::Spark => '*',
+                Kind::Trail => '.',
+            };
+            buf[(area.x + column as u16, area.y + row as u16)]
+                .set_char(glyph)
+                .set_fg(color);
+        }
+    }
+}
+
+/// A show for a web page to run in xterm.js: the page hands it the size of
+/// the terminal, what is typed into it and the time, and writes back
+/// whatever `frame` returns.
+#[wasm_bindgen]
+pub struct Show {
+    world: World,
+    terminal: Terminal<Xterm>,
+    rng: StdRng,
+    // seconds of simulation time not taken yet, and until the next launch
+    pending: f64,
+    next_launch: f64,
+    paused: bool,
+}
+
+#[wasm_bindgen]
+impl Show {
+    #[wasm_bindgen(constructor)]
+    pub fn new(columns: u16, rows: u16, seed: u32) -> Show {
+        let mut world = World::new();
+        world.set_seed(u64::from(seed));
+        world.set_floor(-f64::from(rows) / 2.0);
+        let terminal = Terminal::new(Xterm::new(columns, rows)).expect("drawing into a string");
+        Show {
+            world,
+            terminal,
+            rng: StdRng::seed_from_u64(u64::from(seed).wrapping_add(1)),
+            pending: 0.0,
+            next_launch: 0.0,
+            paused: false,
+        }
+    }
+
+    /// Follows xterm.js's `onResize`.
+    pub fn resize(&mut self, columns: u16, rows: u16) {
+        self.terminal.backend_mut().resize(columns, rows);
+        self.world.set_floor(-f64::from(rows) / 2.0);
+    }
+
+    /// Takes what xterm.js's `onData` reads: `f` or Enter launches a rocket,
+    /// `m` a few at once, `1` to `9` one of that color and the space bar
+    /// pauses.
+    pub fn input(&mut self, data: &str) {
+        for c in data.chars() {
+            match c {
+                'f' | '\r' => self.launch(None),
+                'm' => {
+                    for _ in 0..5 {
+                        self.launch(None);
+                    }
+                }
+                ' ' | 'p' => self.paused = !self.paused,
+                '1'..='9' => {
+                    let color = c as usize - '1' as usize;
+                    if color < self.world.palette().len() {
+                        self.launch(Some(color));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Moves the show on by `secs` and draws it, returning the text to write
+    /// to the terminal.
+    pub fn frame(&mut self, secs: f64) -> String {
+        if !self.paused {
+            self.pending += secs.clamp(0.0, MAX_FRAME);
+            while self.pending >= REFERENCE_DT {
+                self.pending -= REFERENCE_DT;
+                self.next_launch -= REFERENCE_DT;
+                if self.next_launch <= 0.0 {
+                    self.launch(None);
+                    self.next_launch = self.rng.gen_range(LAUNCH_EVERY.0..LAUNCH_EVERY.1);
+                }
+                self.world.step(REFERENCE_DT);
+            }
+        }
+        let world = &self.world;
+        // drawing into a string can't fail
+        let _ = self
+            .terminal
+            .draw(|f| f.render_widget(Sky(world), f.area()));
+        self.terminal.backend_mut().take()
+    }
+
+    fn launch(&mut self, color: Option<usize>) {
+        let Size { width, height } = self.terminal.backend().size;
+        if width < MIN_SIZE || height < MIN_SIZE {
+            return;
+        }
+        let (width, height) = (f64::from(width), f64::from(height));
+        // the speeds the program picks from on a screen this high
+        let max_speed = 8.0 * height.sqrt();
+        let color = color.unwrap_or_else(|| self.rng.gen_range(0..self.world.palette().len()));
+        let pos = Vector {
+            x: self.rng.gen_range(-width / 2.0..width / 2.0),
+            y: -height / 2.0,
+        };
+        let vel = Vector {
+            x: self.rng.gen_range(-8.0..8.0),
+            y: self.rng.gen_range(max_speed * 0.8..max_speed),
+        };
+        self.world.launch(color, pos, vel);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draws_into_escape_codes() {
+        let mut show = Show::new(40, 20, 7);
+        assert_eq!(show.frame(0.0), "\x1b[?25l");
+        show.input("m");
+        let mut drawn = String::new();
+        for _ in 0..60 {
+            drawn += &show.frame(1.0 / 30.0);
+        }
+        assert!(drawn.contains('*'));
+        // nothing moves while paused, so nothing is drawn again
+        show.input(" ");
+        show.frame(1.0 / 30.0);
+        assert_eq!(show.frame(1.0 / 30.0), "");
+    }
+
+    #[test]
+    fn launches_nothing_on_a_tiny_terminal() {
+        let mut show = Show::new(40, 20, 7);
+        show.resize(0, 0);
+        show.input("fm1");
+        show.frame(2.0);
+        assert_eq!(show.world.particle_count(), 0);
+        show.resize(40, 20);
+        show.input("f");
+        assert_eq!(show.world.particle_count(), 1);
+    }
+}